@@ -1,9 +1,9 @@
 wit_bindgen::generate!({ generate_all });
 
-use crate::betty_blocks::data_api::data_api::request;
+use crate::betty_blocks::data_api::data_api::{request, request_batch};
 use crate::exports::data_api::crud::crud::{
-    Guest, HelperContext, JsonString, Model, ObjectField, PropertyKey, PropertyKind, PropertyMap,
-    PropertyMapping,
+    DataApiError, Guest, HelperContext, JsonString, Model, ObjectField, PropertyKey, PropertyKind,
+    PropertyMap, PropertyMapping,
 };
 
 #[derive(Eq, PartialEq, Debug)]
@@ -221,12 +221,7 @@ fn parse_assigned_properties(property_map: PropertyMapping) -> serde_json::Value
     serde_json::Value::Object(result)
 }
 
-fn fetch_record(
-    helper_context: HelperContext,
-    model_name: &str,
-    id: &str,
-    fragment: &GraphQL,
-) -> Result<JsonString, String> {
+fn fetch_record_query(model_name: &str, id: &str, fragment: &GraphQL) -> (String, String) {
     let query_name = format!("one{model_name}",);
     let GraphQL { name, gql } = fragment;
 
@@ -245,25 +240,40 @@ fn fetch_record(
         }}"#,
     );
 
-    let result = request(
-        &helper_context,
-        &query,
-        &serde_json::json!(
-        {
-            "where": {
-                "id" : {
-                    "eq": id
-                },
+    let variables = serde_json::json!(
+    {
+        "where": {
+            "id" : {
+                "eq": id
             },
-        })
-        .to_string(),
-    );
+        },
+    })
+    .to_string();
+
+    (query, variables)
+}
+
+fn parse_fetched_record(data: &str) -> Result<JsonString, DataApiError> {
+    match serde_json::from_str(data).unwrap() {
+        serde_json::Value::Object(record) => Ok(serde_json::to_string(&record).unwrap()),
+        _ => Err(DataApiError::Internal(
+            "Return type of provider should always be an object".to_string(),
+        )),
+    }
+}
+
+fn fetch_record(
+    helper_context: HelperContext,
+    model_name: &str,
+    id: &str,
+    fragment: &GraphQL,
+) -> Result<JsonString, DataApiError> {
+    let (query, variables) = fetch_record_query(model_name, id, fragment);
+
+    let result = request(&helper_context, &query, &variables);
 
     match result {
-        Ok(data) => match serde_json::from_str(&data).unwrap() {
-            serde_json::Value::Object(record) => Ok(serde_json::to_string(&record).unwrap()),
-            _ => Err("Return type of provider should always be an object".to_string()),
-        },
+        Ok(data) => parse_fetched_record(&data),
         Err(e) => Err(e),
     }
 }
@@ -324,7 +334,7 @@ fn get_affected_record(
     helper_context: HelperContext,
     model_name: &str,
     fragment: &GraphQL,
-) -> Result<String, String> {
+) -> Result<String, DataApiError> {
     let id = get_record_id(request_result, mutation_name)
         .expect("Succesfull create should always return an id");
 
@@ -339,7 +349,7 @@ impl Guest for CrudComponent {
         model: Model,
         mapping: PropertyMapping,
         validation_sets: Option<Vec<String>>,
-    ) -> Result<JsonString, String> {
+    ) -> Result<JsonString, DataApiError> {
         let fragment = parse_to_gql_fragment(&model.name, mapping.clone());
 
         let assign_properties = parse_assigned_properties(mapping.clone());
@@ -378,7 +388,7 @@ impl Guest for CrudComponent {
         record_id: String,
         mapping: PropertyMapping,
         validation_sets: Option<Vec<String>>,
-    ) -> Result<JsonString, String> {
+    ) -> Result<JsonString, DataApiError> {
         let fragment = parse_to_gql_fragment(&model.name, mapping.clone());
 
         let assign_properties = parse_assigned_properties(mapping.clone());
@@ -394,21 +404,21 @@ impl Guest for CrudComponent {
             }
         );
 
-        let result = request(
-            &helper_context.clone(),
-            &mutation,
-            &serde_json::to_string(&input).unwrap(),
-        );
+        // NOTE:
+        // The record id is already known, so the updated record is fetched in the same batch
+        let queries = [
+            (mutation, serde_json::to_string(&input).unwrap()),
+            fetch_record_query(&model.name, &record_id, &fragment),
+        ];
 
-        match result {
-            Ok(data) => get_affected_record(
-                &data,
-                &mutation_name,
-                helper_context,
-                &model.name,
-                &fragment,
-            ),
-            Err(e) => Err(e),
+        let mut results = request_batch(&helper_context, &queries)?.into_iter();
+
+        match (results.next(), results.next()) {
+            (Some(Ok(_)), Some(Ok(data))) => parse_fetched_record(&data),
+            (Some(Err(e)), _) | (_, Some(Err(e))) => Err(e),
+            _ => Err(DataApiError::Internal(
+                "Batch should return a result for every query".to_string(),
+            )),
         }
     }
 
@@ -416,7 +426,7 @@ impl Guest for CrudComponent {
         helper_context: HelperContext,
         model: Model,
         record_id: String,
-    ) -> Result<JsonString, String> {
+    ) -> Result<JsonString, DataApiError> {
         let mutation_name = format!("delete{}", model.name);
         let mutation = format_delete_mutation(&mutation_name);
         let input = serde_json::json!(
@@ -1055,6 +1065,32 @@ mutation ($id: Int!) {
         );
     }
 
+    #[test]
+    fn fetch_record_query_should_filter_on_id() {
+        let fragment = GraphQL {
+            name: "userFields".to_string(),
+            gql: "".to_string(),
+        };
+
+        let (query, variables) = fetch_record_query("user", "42", &fragment);
+
+        assert_eq!(
+            graphql_minify::minify(query),
+            graphql_minify::minify(
+                r#"
+query ($where: userFilterInput) {
+  oneuser(where: $where) {
+    ...userFields
+  }
+}"#
+            ),
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&variables).unwrap(),
+            json!({"where": {"id": {"eq": "42"}}})
+        );
+    }
+
     #[test]
     fn get_record_id_should_get_id_from_request_result() {
         let mutation_name = "createuser";
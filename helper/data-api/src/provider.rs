@@ -1,16 +1,28 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
 use anyhow::Context as _;
 use wasmcloud_provider_sdk::initialize_observability;
 use wasmcloud_provider_sdk::{
     run_provider, serve_provider_exports, Context, Provider, ProviderInitConfig,
 };
+use wit_bindgen_wrpc::bytes::Bytes;
+use wit_bindgen_wrpc::wrpc_transport::{ResourceBorrow, ResourceOwn};
 
 pub(crate) mod bindings {
     wit_bindgen_wrpc::generate!();
 }
 
-use bindings::exports::betty_blocks::data_api::data_api::{Handler, HelperContext};
+use bindings::exports::betty_blocks::data_api::data_api::{
+    Cursor, DataApiError, Handler, HandlerCursor, HelperContext,
+};
 #[derive(Default, Clone)]
-pub struct GraphqlProvider {}
+pub struct GraphqlProvider {
+    /// Results of `request-stream` that have not been read yet, sent as a single chunk.
+    cursors: Arc<Mutex<HashMap<Bytes, String>>>,
+    next_cursor: Arc<AtomicU64>,
+}
 
 impl GraphqlProvider {
     fn name() -> &'static str {
@@ -47,7 +59,7 @@ impl Handler<Option<Context>> for GraphqlProvider {
         _helper_context: HelperContext,
         query: String,
         _variables: String,
-    ) -> anyhow::Result<Result<String, String>> {
+    ) -> anyhow::Result<Result<String, DataApiError>> {
         if query.contains("create") {
             return Ok(Ok(serde_json::json!({
                 "createtest": {
@@ -73,6 +85,69 @@ impl Handler<Option<Context>> for GraphqlProvider {
         })
         .to_string()))
     }
+    async fn request_batch(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        queries: Vec<(String, String)>,
+    ) -> anyhow::Result<Result<Vec<Result<String, DataApiError>>, DataApiError>> {
+        let mut results = vec![];
+        for (query, variables) in queries {
+            let result = self
+                .request(ctx.clone(), helper_context.clone(), query, variables)
+                .await?;
+            results.push(result);
+        }
+
+        Ok(Ok(results))
+    }
+    async fn request_stream(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        query: String,
+        variables: String,
+    ) -> anyhow::Result<Result<ResourceOwn<Cursor>, DataApiError>> {
+        let result = match self.request(ctx, helper_context, query, variables).await? {
+            Ok(result) => result,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        let handle = Bytes::from(
+            self.next_cursor
+                .fetch_add(1, Ordering::Relaxed)
+                .to_be_bytes()
+                .to_vec(),
+        );
+        self.cursors
+            .lock()
+            .expect("cursors lock is not poisoned")
+            .insert(handle.clone(), result);
+
+        Ok(Ok(ResourceOwn::new(handle)))
+    }
+    async fn decrypt_configurations(
+        &self,
+        _ctx: Option<Context>,
+        helper_context: HelperContext,
+    ) -> anyhow::Result<Result<Vec<String>, DataApiError>> {
+        // NOTE:
+        // The mock does not encrypt, configurations are returned as they were sent
+        Ok(Ok(helper_context
+            .encrypted_configurations
+            .unwrap_or_default()))
+    }
+}
+
+impl HandlerCursor<Option<Context>> for GraphqlProvider {
+    async fn next(
+        &self,
+        _ctx: Option<Context>,
+        cursor: ResourceBorrow<Cursor>,
+    ) -> anyhow::Result<Result<Option<String>, DataApiError>> {
+        let mut cursors = self.cursors.lock().expect("cursors lock is not poisoned");
+        Ok(Ok(cursors.remove::<Bytes>(cursor.as_ref())))
+    }
 }
 
 impl Provider for GraphqlProvider {
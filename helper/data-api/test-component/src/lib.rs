@@ -18,6 +18,7 @@ impl Guest for TestComponent {
             log_id: "test".to_string(),
             jwt: None,
             encrypted_configurations: None,
+            timeout_ms: None,
         };
 
         let model = Model {
@@ -44,7 +45,7 @@ impl Guest for TestComponent {
                 Ok("success".to_string())
             }
             Err(e) => {
-                info!("Error: {}", e);
+                info!("Error: {:?}", e);
                 Err("success".to_string())
             }
         }
@@ -59,6 +60,7 @@ impl Guest for TestComponent {
             log_id: "test".to_string(),
             jwt: None,
             encrypted_configurations: None,
+            timeout_ms: None,
         };
 
         let model = Model {
@@ -85,7 +87,7 @@ impl Guest for TestComponent {
                 Ok("success".to_string())
             }
             Err(e) => {
-                info!("Error: {}", e);
+                info!("Error: {:?}", e);
                 Err("success".to_string())
             }
         }
@@ -100,6 +102,7 @@ impl Guest for TestComponent {
             log_id: "test".to_string(),
             jwt: None,
             encrypted_configurations: None,
+            timeout_ms: None,
         };
 
         let model = Model {
@@ -118,7 +121,7 @@ impl Guest for TestComponent {
                 Ok("success".to_string())
             }
             Err(e) => {
-                info!("Error: {}", e);
+                info!("Error: {:?}", e);
                 Err("success".to_string())
             }
         }
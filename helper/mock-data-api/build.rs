@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // NOTE:
+    // The proto of the provider is the contract, so the mock always implements the same service
+    tonic_build::configure().compile_protos(
+        &["../../providers/data-api/proto/data-api.proto"],
+        &["../../providers/data-api/proto"],
+    )?;
+    Ok(())
+}
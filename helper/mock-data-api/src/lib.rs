@@ -0,0 +1,295 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use data_grpc::data_api_result::Status as ResultStatus;
+use data_grpc::data_api_server::{DataApi, DataApiServer};
+use data_grpc::{Context, DataApiBatchRequest, DataApiBatchResult, DataApiRequest, DataApiResult};
+use tokio::net::TcpListener;
+use tokio_stream::Stream;
+use tonic::codec::CompressionEncoding;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+pub mod data_grpc {
+    tonic::include_proto!("data_grpc"); // The string specified here must match the proto package name
+}
+
+mod store;
+
+pub use store::Store;
+
+// Stays well below the default message limit of gRPC.
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The `DataAPI` service, executing queries against an in-memory [`Store`].
+#[derive(Clone)]
+pub struct MockDataApi {
+    store: Arc<Mutex<Store>>,
+    chunk_size: usize,
+}
+
+impl Default for MockDataApi {
+    fn default() -> Self {
+        Self {
+            store: Arc::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl MockDataApi {
+    /// Size in bytes of the chunks that `ExecuteStream` splits results into.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Responses are compressed with whichever encoding the client accepts.
+    pub fn into_server(self) -> DataApiServer<Self> {
+        DataApiServer::new(self)
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd)
+            .send_compressed(CompressionEncoding::Gzip)
+            .send_compressed(CompressionEncoding::Zstd)
+    }
+
+    fn execute_query(
+        &self,
+        context: Option<&Context>,
+        query: &str,
+        variables: &str,
+    ) -> DataApiResult {
+        let application_id = context.map_or("", |context| &context.application_id);
+        let result = self
+            .store
+            .lock()
+            .expect("store lock is not poisoned")
+            .execute(application_id, query, variables);
+
+        match result {
+            Ok(data) => DataApiResult {
+                status: ResultStatus::Ok.into(),
+                result: data.to_string(),
+            },
+            Err(message) => DataApiResult {
+                status: ResultStatus::Error.into(),
+                result: serde_json::json!({ "errors": [{ "message": message }] }).to_string(),
+            },
+        }
+    }
+}
+
+/// Serves the mock, and the gRPC health-check service reporting it as serving, until the
+/// listener fails.
+pub async fn serve(listener: TcpListener, data_api: MockDataApi) -> anyhow::Result<()> {
+    info!("mock data-api listening on {}", listener.local_addr()?);
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<DataApiServer<MockDataApi>>()
+        .await;
+
+    Server::builder()
+        .add_service(health_service)
+        .add_service(data_api.into_server())
+        .serve_with_incoming(TcpIncoming::from(listener))
+        .await?;
+    Ok(())
+}
+
+/// The token is not verified, the mock only checks that the provider sent one.
+fn is_authorized<T>(request: &Request<T>) -> bool {
+    request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("Bearer "))
+}
+
+/// Splits `result` in chunks of at most `size` bytes, without splitting characters.
+fn chunks(result: &str, size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+
+    for c in result.chars() {
+        if !chunk.is_empty() && chunk.len() + c.len_utf8() > size {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+#[tonic::async_trait]
+impl DataApi for MockDataApi {
+    type ExecuteStreamStream = Pin<Box<dyn Stream<Item = Result<DataApiResult, Status>> + Send>>;
+
+    async fn execute(
+        &self,
+        request: Request<DataApiRequest>,
+    ) -> Result<Response<DataApiResult>, Status> {
+        if !is_authorized(&request) {
+            return Err(Status::unauthenticated("missing bearer token"));
+        }
+
+        let request = request.into_inner();
+        Ok(Response::new(self.execute_query(
+            request.context.as_ref(),
+            &request.query,
+            &request.variables,
+        )))
+    }
+
+    async fn execute_batch(
+        &self,
+        request: Request<DataApiBatchRequest>,
+    ) -> Result<Response<DataApiBatchResult>, Status> {
+        if !is_authorized(&request) {
+            return Err(Status::unauthenticated("missing bearer token"));
+        }
+
+        let request = request.into_inner();
+        let results = request
+            .queries
+            .iter()
+            .map(|query| {
+                self.execute_query(request.context.as_ref(), &query.query, &query.variables)
+            })
+            .collect();
+
+        Ok(Response::new(DataApiBatchResult { results }))
+    }
+
+    async fn execute_stream(
+        &self,
+        request: Request<DataApiRequest>,
+    ) -> Result<Response<Self::ExecuteStreamStream>, Status> {
+        if !is_authorized(&request) {
+            return Err(Status::unauthenticated("missing bearer token"));
+        }
+
+        let request = request.into_inner();
+        let result =
+            self.execute_query(request.context.as_ref(), &request.query, &request.variables);
+
+        let messages: Vec<_> = match result.status() {
+            ResultStatus::Ok => chunks(&result.result, self.chunk_size)
+                .into_iter()
+                .map(|chunk| DataApiResult {
+                    status: ResultStatus::Ok.into(),
+                    result: chunk,
+                })
+                .collect(),
+            ResultStatus::Error => vec![result],
+        };
+
+        Ok(Response::new(Box::pin(tokio_stream::iter(
+            messages.into_iter().map(Ok),
+        ))))
+    }
+}
+
+#[cfg(test)]
+fn authorized<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer token".parse().unwrap());
+    request
+}
+
+#[test]
+fn test_chunks() {
+    assert_eq!(chunks("abcde", 2), vec!["ab", "cd", "e"]);
+    assert_eq!(chunks("aé", 2), vec!["a", "é"]);
+    assert!(chunks("", 2).is_empty());
+}
+
+#[tokio::test]
+async fn test_execute_requires_authorization() {
+    let data_api = MockDataApi::default();
+    let request = DataApiRequest {
+        query: String::from("{allUser{results{id}}}"),
+        variables: String::new(),
+        context: None,
+    };
+
+    let status = data_api
+        .execute(Request::new(request.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    let result = data_api.execute(authorized(request)).await.unwrap();
+    assert_eq!(result.get_ref().status(), ResultStatus::Ok);
+}
+
+#[tokio::test]
+async fn test_execute_batch() {
+    let data_api = MockDataApi::default();
+    let context = Context {
+        application_id: String::from("app"),
+        jwt: String::new(),
+    };
+
+    let result = data_api
+        .execute_batch(authorized(DataApiBatchRequest {
+            queries: vec![
+                data_grpc::Query {
+                    query: String::from(
+                        "mutation($input: UserInput) { createUser(input: $input) { id } }",
+                    ),
+                    variables: String::from(r#"{"input": {"name": "Betty"}}"#),
+                },
+                data_grpc::Query {
+                    query: String::from("{ deleteUser(id: 2) { id } }"),
+                    variables: String::from(r#"{"id": 2}"#),
+                },
+            ],
+            context: Some(context),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(result.results.len(), 2);
+    assert_eq!(result.results[0].result, r#"{"createUser":{"id":"1"}}"#);
+    assert_eq!(result.results[1].status(), ResultStatus::Error);
+    assert_eq!(
+        result.results[1].result,
+        r#"{"errors":[{"message":"User 2 does not exist"}]}"#
+    );
+}
+
+#[tokio::test]
+async fn test_execute_stream() {
+    use tokio_stream::StreamExt;
+
+    let data_api = MockDataApi::default().with_chunk_size(4);
+
+    let stream = data_api
+        .execute_stream(authorized(DataApiRequest {
+            query: String::from("{allUser{results{id}}}"),
+            variables: String::new(),
+            context: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let chunks = stream
+        .map(|message| message.unwrap().result)
+        .collect::<Vec<_>>()
+        .await;
+    assert!(chunks.len() > 1);
+    assert_eq!(
+        chunks.concat(),
+        r#"{"allUser":{"results":[],"totalCount":0}}"#
+    );
+}
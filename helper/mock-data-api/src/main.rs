@@ -0,0 +1,20 @@
+use anyhow::Context as _;
+use mock_data_api::MockDataApi;
+use tokio::net::TcpListener;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:50054";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let address =
+        std::env::var("MOCK_DATA_API_ADDRESS").unwrap_or_else(|_| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address)
+        .await
+        .with_context(|| format!("failed to listen on {address}"))?;
+
+    mock_data_api::serve(listener, MockDataApi::default()).await?;
+    eprintln!("Mock data-api exiting");
+    Ok(())
+}
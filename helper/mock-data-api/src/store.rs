@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Map, Value};
+
+type Record = Map<String, Value>;
+
+/// The operations of the crud component, with the name of the model they act on.
+#[derive(Debug, PartialEq)]
+pub enum Operation<'a> {
+    Create(&'a str),
+    Update(&'a str),
+    Delete(&'a str),
+    One(&'a str),
+    All(&'a str),
+}
+
+impl<'a> Operation<'a> {
+    pub fn parse(field: &'a str) -> Option<Self> {
+        let model = |prefix| field.strip_prefix(prefix).filter(|model| !model.is_empty());
+
+        model("create")
+            .map(Self::Create)
+            .or_else(|| model("update").map(Self::Update))
+            .or_else(|| model("delete").map(Self::Delete))
+            .or_else(|| model("one").map(Self::One))
+            .or_else(|| model("all").map(Self::All))
+    }
+}
+
+/// Name of the first field selected by the operation of `query`, fragment definitions are
+/// skipped.
+pub fn root_field(query: &str) -> Option<&str> {
+    let mut depth = 0;
+    let mut in_fragment = false;
+
+    for (index, c) in query.char_indices() {
+        match c {
+            '{' if depth == 0 && !in_fragment => {
+                let selection = query[index + 1..].trim_start();
+                let end = selection
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(selection.len());
+                return Some(&selection[..end]).filter(|field| !field.is_empty());
+            }
+            '{' => {
+                in_fragment = false;
+                depth += 1;
+            }
+            '}' => depth -= 1,
+            _ if depth == 0 && query[index..].starts_with("fragment") => in_fragment = true,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Records of every model, per application.
+#[derive(Debug, Default)]
+pub struct Store {
+    models: HashMap<(String, String), BTreeMap<u64, Record>>,
+}
+
+impl Store {
+    /// Executes the query and returns the `data` of its result, or the message of the error.
+    pub fn execute(
+        &mut self,
+        application_id: &str,
+        query: &str,
+        variables: &str,
+    ) -> Result<Value, String> {
+        let variables: Value = match variables.trim() {
+            "" => json!({}),
+            variables => serde_json::from_str(variables)
+                .map_err(|e| format!("variables are not valid json: {e}"))?,
+        };
+
+        let field = root_field(query).ok_or("the query does not select a field")?;
+        let operation =
+            Operation::parse(field).ok_or_else(|| format!("unknown operation: {field}"))?;
+
+        let data = match operation {
+            Operation::Create(model) => {
+                let records = self.records(application_id, model);
+                let id = records.keys().next_back().map_or(1, |id| id + 1);
+
+                let mut record = input(&variables);
+                record.insert(String::from("id"), json!(id.to_string()));
+                records.insert(id, record);
+
+                json!({ "id": id.to_string() })
+            }
+            Operation::Update(model) => {
+                let id = record_id(&variables["id"])?;
+                let record = self
+                    .records(application_id, model)
+                    .get_mut(&id)
+                    .ok_or_else(|| format!("{model} {id} does not exist"))?;
+
+                record.extend(input(&variables));
+                record.insert(String::from("id"), json!(id.to_string()));
+
+                json!({ "id": id.to_string() })
+            }
+            Operation::Delete(model) => {
+                let id = record_id(&variables["id"])?;
+                self.records(application_id, model)
+                    .remove(&id)
+                    .ok_or_else(|| format!("{model} {id} does not exist"))?;
+
+                json!({ "id": id.to_string() })
+            }
+            Operation::One(model) => {
+                let id = record_id(&variables["where"]["id"]["eq"])?;
+                json!(self.records(application_id, model).get(&id))
+            }
+            Operation::All(model) => {
+                let records = self.records(application_id, model);
+                json!({
+                    "results": records.values().collect::<Vec<_>>(),
+                    "totalCount": records.len(),
+                })
+            }
+        };
+
+        Ok(json!({ field: data }))
+    }
+
+    fn records(&mut self, application_id: &str, model: &str) -> &mut BTreeMap<u64, Record> {
+        self.models
+            .entry((application_id.to_string(), model.to_string()))
+            .or_default()
+    }
+}
+
+fn input(variables: &Value) -> Record {
+    variables["input"].as_object().cloned().unwrap_or_default()
+}
+
+/// Ids are sent as strings by the crud component, but as numbers by hand written queries.
+fn record_id(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::String(id) => id.parse().ok(),
+        Value::Number(id) => id.as_u64(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("invalid record id: {value}"))
+}
+
+#[test]
+fn test_root_field() {
+    assert_eq!(root_field("{allUser{results{id}}}"), Some("allUser"));
+    assert_eq!(
+        root_field(
+            "mutation($input: UserInput, $validationSets: [String]) {
+                createUser(input: $input, validationSets: $validationSets) { id }
+            }"
+        ),
+        Some("createUser")
+    );
+    assert_eq!(
+        root_field(
+            "fragment userFields on User { name { first } }
+            query($where: UserFilterInput) { oneUser(where: $where) { ...userFields } }"
+        ),
+        Some("oneUser")
+    );
+    assert_eq!(root_field("fragment userFields on User { id }"), None);
+    assert_eq!(root_field("{ }"), None);
+}
+
+#[test]
+fn test_operation_parse() {
+    assert_eq!(
+        Operation::parse("createUser"),
+        Some(Operation::Create("User"))
+    );
+    assert_eq!(Operation::parse("oneuser"), Some(Operation::One("user")));
+    assert_eq!(Operation::parse("all"), None);
+    assert_eq!(Operation::parse("upsertUser"), None);
+}
+
+#[test]
+fn test_store_crud() {
+    let mut store = Store::default();
+    let create = "mutation($input: UserInput) { createUser(input: $input) { id } }";
+    let update =
+        "mutation($id: Int!, $input: UserInput) { updateUser(id: $id, input: $input) { id } }";
+    let delete = "mutation($id: Int!) { deleteUser(id: $id) { id } }";
+    let one = "query($where: UserFilterInput) { oneUser(where: $where) { id } }";
+
+    let created = store
+        .execute("app", create, r#"{"input": {"name": "Betty"}}"#)
+        .unwrap();
+    assert_eq!(created, json!({"createUser": {"id": "1"}}));
+
+    let updated = store
+        .execute("app", update, r#"{"id": "1", "input": {"age": 30}}"#)
+        .unwrap();
+    assert_eq!(updated, json!({"updateUser": {"id": "1"}}));
+
+    let fetched = store
+        .execute("app", one, r#"{"where": {"id": {"eq": "1"}}}"#)
+        .unwrap();
+    assert_eq!(
+        fetched,
+        json!({"oneUser": {"id": "1", "name": "Betty", "age": 30}})
+    );
+
+    // applications do not share records
+    let fetched = store
+        .execute("other-app", one, r#"{"where": {"id": {"eq": 1}}}"#)
+        .unwrap();
+    assert_eq!(fetched, json!({"oneUser": null}));
+
+    let all = store.execute("app", "{allUser{results{id}}}", "").unwrap();
+    assert_eq!(all["allUser"]["totalCount"], json!(1));
+
+    store.execute("app", delete, r#"{"id": 1}"#).unwrap();
+    assert!(store.execute("app", delete, r#"{"id": 1}"#).is_err());
+    assert!(store.execute("app", update, r#"{"id": 1}"#).is_err());
+}
+
+#[test]
+fn test_store_errors() {
+    let mut store = Store::default();
+
+    assert!(store
+        .execute("app", "{upsertUser{id}}", "{}")
+        .unwrap_err()
+        .contains("unknown operation"));
+    assert!(store.execute("app", "{allUser{id}}", "{").is_err());
+    assert!(store
+        .execute("app", "mutation { deleteUser(id: $id) { id } }", "{}")
+        .is_err());
+}
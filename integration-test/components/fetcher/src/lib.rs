@@ -15,7 +15,7 @@ pub mod bindings {
     wit_bindgen::generate!({ generate_all });
 }
 
-use crate::data_api::HelperContext;
+use crate::data_api::{DataApiError, HelperContext};
 use bindings::betty_blocks::data_api::data_api;
 
 #[derive(Debug)]
@@ -24,6 +24,29 @@ enum Routes {
     Data,
 }
 
+fn error_body(error: DataApiError) -> String {
+    let errors = match error {
+        DataApiError::Validation(errors) => errors
+            .into_iter()
+            .map(|error| match error.extensions {
+                Some(extensions) => serde_json::json!({
+                    "message": error.message,
+                    "extensions": serde_json::from_str::<serde_json::Value>(&extensions)
+                        .unwrap_or(serde_json::Value::String(extensions)),
+                }),
+                None => serde_json::json!({ "message": error.message }),
+            })
+            .collect(),
+        DataApiError::Unauthorized(message) => vec![serde_json::json!({
+            "message": message,
+            "extensions": { "code": "UNAUTHENTICATED" },
+        })],
+        error => vec![serde_json::json!({ "message": format!("{error:?}") })],
+    };
+
+    serde_json::json!({ "errors": errors }).to_string()
+}
+
 impl http::Server for Component {
     fn handle(
         request: http::IncomingRequest,
@@ -55,11 +78,12 @@ impl http::Server for Component {
                     action_id: "feae0f99dd4c423aae4d174fcd63ccdf".to_string(),
                     log_id: "123545".to_string(),
                     encrypted_configurations: None,
+                    timeout_ms: None,
                 };
                 match data_api::request(&helper_context, "{allUser{results{id}}}", "{}") {
                     Ok(result) => result,
-                    Err(result) => {
-                        let mut response = http::Response::new(result);
+                    Err(error) => {
+                        let mut response = http::Response::new(error_body(error));
                         *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
                         return Ok(response);
                     }
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A fixed set of gRPC channels to a single data-api address, handed out round-robin.
+///
+/// The channels connect lazily on first use. Each one multiplexes requests over its own
+/// HTTP/2 connection and reconnects by itself when that connection drops, so a pool can be
+/// shared by all handler invocations for the lifetime of the provider.
+#[derive(Clone, Debug)]
+pub struct ChannelPool {
+    channels: Arc<[Channel]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl ChannelPool {
+    pub fn new(
+        address: &str,
+        size: usize,
+        keep_alive_interval: Duration,
+        tls: Option<ClientTlsConfig>,
+    ) -> anyhow::Result<Self> {
+        let mut endpoint = Endpoint::from_shared(address.to_string())?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .http2_keep_alive_interval(keep_alive_interval)
+            .keep_alive_while_idle(true);
+
+        if let Some(tls) = tls {
+            endpoint = endpoint.tls_config(tls)?;
+        }
+
+        let channels = (0..size.max(1))
+            .map(|_| endpoint.connect_lazy())
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            channels: channels.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn channel(&self) -> Channel {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.channels.len();
+        self.channels[index].clone()
+    }
+
+    pub fn size(&self) -> usize {
+        self.channels.len()
+    }
+}
+
+#[tokio::test]
+async fn test_channel_pool_connects_lazily() -> anyhow::Result<()> {
+    // nothing listens on this port, creating the pool should still succeed
+    let pool = ChannelPool::new("http://127.0.0.1:1", 3, Duration::from_secs(30), None)?;
+    assert_eq!(pool.size(), 3);
+
+    let pool = ChannelPool::new("http://127.0.0.1:1", 0, Duration::from_secs(30), None)?;
+    assert_eq!(pool.size(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_channel_pool_rejects_invalid_address() {
+    assert!(ChannelPool::new("not a uri", 1, Duration::from_secs(30), None).is_err());
+}
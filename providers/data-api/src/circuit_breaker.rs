@@ -0,0 +1,125 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+}
+
+/// Fast-fails requests while the data-api is down.
+///
+/// After `failure_threshold` consecutive transient failures the breaker opens and rejects every
+/// request for `reset_timeout`. After that a single trial request is let through and the breaker
+/// stays open for another `reset_timeout`: when the trial succeeds the breaker closes, when it
+/// fails the breaker reopens.
+/// A `failure_threshold` of 0 disables the breaker.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            failure_threshold,
+            reset_timeout,
+            state: Arc::new(Mutex::new(State::Closed { failures: 0 })),
+        }
+    }
+
+    pub fn allow(&self) -> bool {
+        if self.failure_threshold == 0 {
+            return true;
+        }
+
+        let mut state = self
+            .state
+            .lock()
+            .expect("circuit breaker lock is not poisoned");
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until } if Instant::now() >= until => {
+                *state = State::Open {
+                    until: Instant::now() + self.reset_timeout,
+                };
+                true
+            }
+            State::Open { .. } => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("circuit breaker lock is not poisoned");
+        *state = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut state = self
+            .state
+            .lock()
+            .expect("circuit breaker lock is not poisoned");
+        *state = match *state {
+            State::Closed { failures } if failures + 1 < self.failure_threshold => State::Closed {
+                failures: failures + 1,
+            },
+            _ => State::Open {
+                until: Instant::now() + self.reset_timeout,
+            },
+        };
+    }
+}
+
+#[test]
+fn test_circuit_breaker_opens_after_threshold() {
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+    breaker.record_failure();
+    assert!(breaker.allow());
+
+    breaker.record_failure();
+    assert!(!breaker.allow());
+
+    // a success in between resets the count
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+    breaker.record_failure();
+    breaker.record_success();
+    breaker.record_failure();
+    assert!(breaker.allow());
+}
+
+#[test]
+fn test_circuit_breaker_trial_request() {
+    let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+
+    breaker.record_failure();
+    assert!(!breaker.allow());
+
+    std::thread::sleep(Duration::from_millis(25));
+    // the reset timeout passed, one trial request is allowed
+    assert!(breaker.allow());
+    assert!(!breaker.allow());
+
+    breaker.record_success();
+    assert!(breaker.allow());
+    assert!(breaker.allow());
+}
+
+#[test]
+fn test_circuit_breaker_disabled() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+
+    for _ in 0..10 {
+        breaker.record_failure();
+    }
+    assert!(breaker.allow());
+}
@@ -0,0 +1,82 @@
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use base64::prelude::{Engine, BASE64_STANDARD};
+
+const NONCE_SIZE: usize = 12;
+
+/// Decrypts one of the encrypted-configurations of a helper-context.
+///
+/// A configuration is the base64 encoding of a 12 byte nonce followed by the AES-256-GCM
+/// ciphertext and tag. `key` is the base64 encoded 32 byte key stored in the key-vault.
+///
+/// The id of the application is the associated data of the ciphertext, so configurations
+/// encrypted for one application can not be decrypted for another.
+pub fn decrypt(key: &str, application_id: &str, configuration: &str) -> anyhow::Result<String> {
+    let key = BASE64_STANDARD.decode(key.trim())?;
+    anyhow::ensure!(key.len() == 32, "the configurations key must be 32 bytes");
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+    let encrypted = BASE64_STANDARD.decode(configuration)?;
+    anyhow::ensure!(
+        encrypted.len() > NONCE_SIZE,
+        "the configuration is too short"
+    );
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_SIZE);
+
+    let payload = Payload {
+        msg: ciphertext,
+        aad: application_id.as_bytes(),
+    };
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| anyhow::anyhow!("the configuration could not be decrypted"))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+#[cfg(test)]
+fn encrypt(key: &str, application_id: &str, configuration: &str) -> String {
+    let key = BASE64_STANDARD.decode(key).unwrap();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce = [7; NONCE_SIZE];
+
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend(
+        cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: configuration.as_bytes(),
+                    aad: application_id.as_bytes(),
+                },
+            )
+            .unwrap(),
+    );
+    BASE64_STANDARD.encode(encrypted)
+}
+
+#[test]
+fn test_decrypt() {
+    let key = BASE64_STANDARD.encode([42; 32]);
+    let configuration = encrypt(&key, "app-a", r#"{"api_key":"secret"}"#);
+
+    assert_eq!(
+        decrypt(&key, "app-a", &configuration).unwrap(),
+        r#"{"api_key":"secret"}"#
+    );
+
+    let other_key = BASE64_STANDARD.encode([1; 32]);
+    assert!(decrypt(&other_key, "app-a", &configuration).is_err());
+
+    assert!(decrypt(&BASE64_STANDARD.encode([42; 16]), "app-a", &configuration).is_err());
+    assert!(decrypt(&key, "app-a", "not base64").is_err());
+    assert!(decrypt(&key, "app-a", &BASE64_STANDARD.encode([0; 8])).is_err());
+}
+
+#[test]
+fn test_decrypt_for_another_application() {
+    let key = BASE64_STANDARD.encode([42; 32]);
+    let configuration = encrypt(&key, "app-a", r#"{"api_key":"secret"}"#);
+
+    assert!(decrypt(&key, "app-b", &configuration).is_err());
+}
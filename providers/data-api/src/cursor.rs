@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::future::Cache;
+use tokio::sync::Mutex;
+use wit_bindgen_wrpc::bytes::Bytes;
+
+/// Open result streams of `request-stream`, keyed by the handle of their `cursor` resource.
+///
+/// wRPC does not tell the provider when a component drops a resource, so streams that have not
+/// been read from for `idle_timeout` are closed. At most `max_open` streams are kept open, the
+/// least recently used ones are closed first.
+pub struct CursorStore<T> {
+    streams: Cache<Bytes, Arc<OpenCursor<T>>>,
+}
+
+/// A stream together with who opened it, only they can read from it.
+pub struct OpenCursor<T> {
+    pub application_id: String,
+    pub component: Option<String>,
+    pub stream: Mutex<T>,
+}
+
+// NOTE:
+// Not derived, the streams themselves are not `Clone`
+impl<T> Clone for CursorStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            streams: self.streams.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> CursorStore<T> {
+    pub fn new(idle_timeout: Duration, max_open: u64) -> Self {
+        Self {
+            streams: Cache::builder()
+                .time_to_idle(idle_timeout)
+                .max_capacity(max_open)
+                .build(),
+        }
+    }
+
+    /// Stores the stream opened by `component` for `application_id` and returns the handle of
+    /// its cursor.
+    pub async fn insert(
+        &self,
+        application_id: String,
+        component: Option<String>,
+        stream: T,
+    ) -> Bytes {
+        let handle = Bytes::copy_from_slice(&rand::random::<u128>().to_be_bytes());
+        let cursor = OpenCursor {
+            application_id,
+            component,
+            stream: Mutex::new(stream),
+        };
+        self.streams.insert(handle.clone(), Arc::new(cursor)).await;
+        handle
+    }
+
+    pub async fn get(&self, handle: &Bytes) -> Option<Arc<OpenCursor<T>>> {
+        self.streams.get(handle).await
+    }
+
+    pub async fn remove(&self, handle: &Bytes) {
+        self.streams.invalidate(handle).await;
+    }
+}
+
+#[tokio::test]
+async fn test_cursor_store() {
+    let store = CursorStore::new(Duration::from_secs(60), 16);
+
+    let first = store
+        .insert(String::from("application"), None, vec![1, 2])
+        .await;
+    let second = store
+        .insert(String::from("application"), None, vec![3])
+        .await;
+    assert_ne!(first, second);
+
+    let cursor = store.get(&first).await.expect("cursor exists");
+    assert_eq!(cursor.application_id, "application");
+    assert_eq!(cursor.stream.lock().await.pop(), Some(2));
+    assert_eq!(
+        *store.get(&first).await.unwrap().stream.lock().await,
+        vec![1]
+    );
+
+    store.remove(&first).await;
+    assert!(store.get(&first).await.is_none());
+    assert!(store.get(&second).await.is_some());
+}
+
+#[tokio::test]
+async fn test_cursor_store_expires_idle_streams() {
+    let store = CursorStore::new(Duration::from_millis(10), 16);
+
+    let handle = store.insert(String::from("application"), None, ()).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(store.get(&handle).await.is_none());
+}
+
+#[tokio::test]
+async fn test_cursor_store_is_bounded() {
+    let store = CursorStore::new(Duration::from_secs(60), 2);
+
+    for _ in 0..10 {
+        store.insert(String::from("application"), None, ()).await;
+    }
+    store.streams.run_pending_tasks().await;
+    assert!(store.streams.entry_count() <= 2);
+}
@@ -0,0 +1,181 @@
+use serde::Deserialize;
+use tonic::Code;
+
+use crate::provider::bindings::exports::betty_blocks::data_api::data_api::{
+    DataApiError, GraphqlError,
+};
+
+// GraphQL error codes the data-api uses when the jaws token or the user jwt is rejected.
+const UNAUTHORIZED_CODES: [&str; 2] = ["UNAUTHENTICATED", "FORBIDDEN"];
+
+#[derive(Deserialize)]
+struct ErrorResult {
+    errors: Vec<ErrorResultEntry>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResultEntry {
+    message: String,
+    extensions: Option<serde_json::Value>,
+}
+
+impl ErrorResultEntry {
+    fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+}
+
+/// Maps the `result` of a data-api response with an `ERROR` status to a [`DataApiError`].
+pub fn from_error_result(result: &str) -> DataApiError {
+    let Ok(ErrorResult { errors }) = serde_json::from_str::<ErrorResult>(result) else {
+        return DataApiError::Internal(result.to_string());
+    };
+
+    if let Some(error) = errors.iter().find(|error| {
+        error
+            .code()
+            .is_some_and(|code| UNAUTHORIZED_CODES.contains(&code))
+    }) {
+        return DataApiError::Unauthorized(error.message.clone());
+    }
+
+    DataApiError::Validation(
+        errors
+            .into_iter()
+            .map(|error| GraphqlError {
+                message: error.message,
+                extensions: error.extensions.map(|extensions| extensions.to_string()),
+            })
+            .collect(),
+    )
+}
+
+impl From<tonic::Status> for DataApiError {
+    fn from(status: tonic::Status) -> Self {
+        let message = status.message().to_string();
+        match status.code() {
+            Code::Unavailable => DataApiError::Connection(message),
+            Code::DeadlineExceeded | Code::Cancelled => DataApiError::Timeout(message),
+            Code::Unauthenticated | Code::PermissionDenied => DataApiError::Unauthorized(message),
+            Code::InvalidArgument => DataApiError::Validation(vec![GraphqlError {
+                message,
+                extensions: None,
+            }]),
+            _ => DataApiError::Internal(message),
+        }
+    }
+}
+
+impl From<anyhow::Error> for DataApiError {
+    fn from(error: anyhow::Error) -> Self {
+        DataApiError::Internal(format!("{error:#}"))
+    }
+}
+
+/// Why a single attempt to send a request to the data-api failed.
+#[derive(Debug)]
+pub enum AttemptError {
+    /// The data-api rejected the jaws token with an `UNAUTHENTICATED` status, without executing
+    /// the request.
+    JawsRejected(DataApiError),
+    Failed(DataApiError),
+}
+
+impl AttemptError {
+    pub fn error(&self) -> &DataApiError {
+        match self {
+            AttemptError::JawsRejected(error) | AttemptError::Failed(error) => error,
+        }
+    }
+
+    pub fn into_error(self) -> DataApiError {
+        match self {
+            AttemptError::JawsRejected(error) | AttemptError::Failed(error) => error,
+        }
+    }
+}
+
+// NOTE:
+// GraphQL errors with an `UNAUTHENTICATED` or `FORBIDDEN` code are about the jwt of the user,
+// or come from a request that was already executed, so only the gRPC status counts
+impl From<tonic::Status> for AttemptError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            Code::Unauthenticated => AttemptError::JawsRejected(status.into()),
+            _ => AttemptError::Failed(status.into()),
+        }
+    }
+}
+
+impl From<DataApiError> for AttemptError {
+    fn from(error: DataApiError) -> Self {
+        AttemptError::Failed(error)
+    }
+}
+
+impl From<anyhow::Error> for AttemptError {
+    fn from(error: anyhow::Error) -> Self {
+        AttemptError::Failed(error.into())
+    }
+}
+
+#[test]
+fn test_from_error_result() {
+    let result = r#"{"errors": [{"message": "something went wrong"}]}"#;
+    assert!(matches!(
+        from_error_result(result),
+        DataApiError::Validation(errors)
+            if errors.len() == 1
+                && errors[0].message == "something went wrong"
+                && errors[0].extensions.is_none()
+    ));
+
+    let result = r#"{"errors": [{"message": "Request not authenticated", "extensions": {"code": "UNAUTHENTICATED"}}]}"#;
+    assert!(matches!(
+        from_error_result(result),
+        DataApiError::Unauthorized(message) if message == "Request not authenticated"
+    ));
+
+    assert!(matches!(
+        from_error_result("not json"),
+        DataApiError::Internal(message) if message == "not json"
+    ));
+}
+
+#[test]
+fn test_from_status() {
+    assert!(matches!(
+        DataApiError::from(tonic::Status::unavailable("down")),
+        DataApiError::Connection(message) if message == "down"
+    ));
+    assert!(matches!(
+        DataApiError::from(tonic::Status::deadline_exceeded("slow")),
+        DataApiError::Timeout(_)
+    ));
+    assert!(matches!(
+        DataApiError::from(tonic::Status::unauthenticated("no")),
+        DataApiError::Unauthorized(_)
+    ));
+    assert!(matches!(
+        DataApiError::from(tonic::Status::internal("oops")),
+        DataApiError::Internal(_)
+    ));
+}
+
+#[test]
+fn test_attempt_error() {
+    assert!(matches!(
+        AttemptError::from(tonic::Status::unauthenticated("invalid jaws")),
+        AttemptError::JawsRejected(DataApiError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        AttemptError::from(tonic::Status::permission_denied("no")),
+        AttemptError::Failed(DataApiError::Unauthorized(_))
+    ));
+
+    let result = r#"{"errors": [{"message": "Forbidden", "extensions": {"code": "FORBIDDEN"}}]}"#;
+    assert!(matches!(
+        AttemptError::from(from_error_result(result)),
+        AttemptError::Failed(DataApiError::Unauthorized(_))
+    ));
+}
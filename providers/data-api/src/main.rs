@@ -1,4 +1,15 @@
+mod channel_pool;
+mod circuit_breaker;
+mod configurations;
+mod cursor;
+mod error;
+mod metrics;
 pub mod provider;
+mod rate_limit;
+mod retry;
+mod routing;
+mod signing_key;
+mod tls;
 
 use provider::DataAPIProvider;
 
@@ -0,0 +1,113 @@
+use std::time::Instant;
+
+use wasmcloud_provider_sdk::wasmcloud_tracing::{global, Counter, Histogram, KeyValue};
+
+use crate::provider::bindings::exports::betty_blocks::data_api::data_api::DataApiError;
+
+/// Instruments of the data-api provider, exported through the otel metrics pipeline that
+/// `initialize_observability!` sets up when the host has metrics enabled.
+///
+/// Everything is labelled by application id, so degradation can be alerted on per tenant.
+#[derive(Clone)]
+pub struct Metrics {
+    requests: Counter<u64>,
+    errors: Counter<u64>,
+    duration: Histogram<f64>,
+    request_size: Histogram<u64>,
+    response_size: Histogram<u64>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let meter = global::meter("data-api-provider");
+
+        Self {
+            requests: meter
+                .u64_counter("data_api.requests")
+                .with_description("Requests sent to the data-api")
+                .build(),
+            errors: meter
+                .u64_counter("data_api.errors")
+                .with_description("Requests to the data-api that failed")
+                .build(),
+            duration: meter
+                .f64_histogram("data_api.request.duration")
+                .with_description("Time until the data-api responded, including retries")
+                .with_unit("s")
+                .build(),
+            request_size: meter
+                .u64_histogram("data_api.request.size")
+                .with_description("Size of the queries and variables sent to the data-api")
+                .with_unit("By")
+                .build(),
+            response_size: meter
+                .u64_histogram("data_api.response.size")
+                .with_description("Size of the successful results of the data-api")
+                .with_unit("By")
+                .build(),
+        }
+    }
+
+    /// Records a finished request, `result` holds the size of the response when it succeeded
+    /// and the response was read at once.
+    pub fn record(
+        &self,
+        operation: &'static str,
+        application_id: &str,
+        started: Instant,
+        request_size: usize,
+        result: Result<Option<usize>, &DataApiError>,
+    ) {
+        let attributes = [
+            KeyValue::new("operation", operation),
+            KeyValue::new("application_id", application_id.to_string()),
+            KeyValue::new("outcome", outcome(result)),
+        ];
+
+        self.requests.add(1, &attributes);
+        self.duration
+            .record(started.elapsed().as_secs_f64(), &attributes);
+        self.request_size.record(request_size as u64, &attributes);
+
+        match result {
+            Ok(Some(response_size)) => self.response_size.record(response_size as u64, &attributes),
+            Ok(None) => {}
+            Err(_) => self.errors.add(1, &attributes),
+        }
+    }
+}
+
+fn outcome(result: Result<Option<usize>, &DataApiError>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(DataApiError::Connection(_)) => "connection",
+        Err(DataApiError::Unauthorized(_)) => "unauthorized",
+        Err(DataApiError::Validation(_)) => "validation",
+        Err(DataApiError::Timeout(_)) => "timeout",
+        Err(DataApiError::RateLimited(_)) => "rate_limited",
+        Err(DataApiError::Internal(_)) => "internal",
+    }
+}
+
+#[test]
+fn test_outcome() {
+    assert_eq!(outcome(Ok(Some(10))), "ok");
+    assert_eq!(outcome(Ok(None)), "ok");
+    assert_eq!(
+        outcome(Err(&DataApiError::Timeout(String::from("too slow")))),
+        "timeout"
+    );
+    assert_eq!(
+        outcome(Err(&DataApiError::Validation(vec![]))),
+        "validation"
+    );
+
+    // without a meter provider the instruments are no-ops
+    Metrics::new().record(
+        "request",
+        "application",
+        Instant::now(),
+        10,
+        Err(&DataApiError::Connection(String::from("unavailable"))),
+    );
+}
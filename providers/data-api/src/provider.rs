@@ -1,49 +1,167 @@
 use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Context as _;
 use data_grpc::data_api_client::DataApiClient;
-use data_grpc::DataApiRequest;
+use data_grpc::{DataApiBatchRequest, DataApiRequest, DataApiResult, Query};
+use moka::future::Cache;
 use rand::distr::Alphanumeric;
 use rand::distr::SampleString;
 use tokio::sync::RwLock;
-use tracing::info;
+use tonic::codec::CompressionEncoding;
+use tonic::transport::{Channel, ClientTlsConfig};
+use tonic::Streaming;
+use tonic_health::pb as health_grpc;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tracing::{info, instrument, warn};
 use wasmcloud_provider_sdk::provider::WrpcClient;
-use wasmcloud_provider_sdk::{initialize_observability, load_host_data};
-use wasmcloud_provider_sdk::{run_provider, serve_provider_exports, Context, Provider};
+use wasmcloud_provider_sdk::wasmcloud_tracing::context::TraceContextInjector;
+use wasmcloud_provider_sdk::{initialize_observability, load_host_data, propagate_trace_for_ctx};
+use wasmcloud_provider_sdk::{
+    run_provider, serve_provider_exports, Context, HealthCheckRequest, HealthCheckResponse,
+    Provider, ProviderConfigUpdate,
+};
+use wit_bindgen_wrpc::bytes::Bytes;
+use wit_bindgen_wrpc::wrpc_transport::{ResourceBorrow, ResourceOwn};
 
 pub mod data_grpc {
     tonic::include_proto!("data_grpc"); // The string specified here must match the proto package name
 }
 
+use crate::channel_pool::ChannelPool;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::configurations;
+use crate::cursor::CursorStore;
+use crate::error::{self, AttemptError};
+use crate::metrics::Metrics;
 use crate::provider::bindings::betty_blocks::key_vault::key_vault;
 use crate::provider::data_grpc::data_api_result::Status;
-use bindings::exports::betty_blocks::data_api::data_api::Handler;
-use bindings::exports::betty_blocks::data_api::data_api::HelperContext;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{self, RetryPolicy};
+use crate::routing::RoutingTable;
+use crate::signing_key::{self, KeyRole, KeySet, SecretSource};
+use crate::tls::TlsSettings;
+use bindings::exports::betty_blocks::data_api::data_api::{Cursor, Handler, HandlerCursor};
+use bindings::exports::betty_blocks::data_api::data_api::{DataApiError, HelperContext};
 
-// Set the limit to 8MB, Grpc defaults to 4MB.
-const REQUEST_SIZE_LIMIT: usize = 8 * 1024 * 1024;
+// Grpc defaults to 4MB, which is too small for large GraphQL responses.
+const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_MAX_ENCODING_MESSAGE_SIZE: usize = usize::MAX;
 const DEFAULT_DATA_API_ADDRESS: &str = "http://0.0.0.0:50054";
+const DEFAULT_DATA_API_POOL_SIZE: usize = 4;
+const DEFAULT_DATA_API_KEEP_ALIVE_SECONDS: u64 = 30;
+// How long the data-api accepts a jaws token after it has been issued.
+const DEFAULT_JAWS_TOKEN_LIFETIME_SECONDS: u64 = 5 * 60;
+// Tokens are minted again this long before they would be rejected.
+const DEFAULT_JAWS_TOKEN_REFRESH_MARGIN_SECONDS: u64 = 30;
+const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 100;
+const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 2_000;
+const DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_CIRCUIT_BREAKER_RESET_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 30_000;
+const DEFAULT_CURSOR_IDLE_TIMEOUT_SECONDS: u64 = 60;
+const DEFAULT_MAX_OPEN_CURSORS: u64 = 1_024;
+const DEFAULT_HEALTH_CHECK_TIMEOUT_MS: u64 = 5_000;
+// The service of the proto, the provider does not compile the server stubs to get its name.
+const DATA_API_SERVICE_NAME: &str = "data_grpc.DataAPI";
+// Rate limiting is disabled unless configured.
+const DEFAULT_RATE_LIMIT_REQUESTS_PER_SECOND: f64 = 0.0;
+const DEFAULT_RATE_LIMIT_BURST: u32 = 0;
+const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 0;
 
 pub(crate) mod bindings {
     wit_bindgen_wrpc::generate!({
         with: {
-            "betty-blocks:key-vault/key-vault": generate
+            "betty-blocks:key-vault/key-vault@0.2.0": generate
         }
     });
 }
 
 #[derive(Clone)]
 pub struct DataAPIProvider {
-    static_config: HashMap<String, String>,
+    // swapped as a whole when the config is updated
+    static_config: Arc<std::sync::RwLock<Arc<HashMap<String, String>>>>,
+    // parsed together with the config, invalid key sets fail every request
+    key_set: Arc<std::sync::RwLock<Result<Arc<KeySet>, String>>>,
     wrpc_client: Arc<RwLock<Option<WrpcClient>>>,
+    routing_table: Arc<RwLock<Option<RoutingTable>>>,
+    // one pool and circuit breaker per data-api address
+    channel_pools: Arc<RwLock<HashMap<String, Upstream>>>,
+    jaws_tokens: Cache<(KeyRole, String), String>,
+    cursors: CursorStore<Streaming<DataApiResult>>,
+    metrics: Metrics,
+    rate_limiter: RateLimiter,
+}
+
+/// The channels to a data-api address, with the circuit breaker of that address so one
+/// unavailable cluster does not fail the requests routed to other clusters.
+#[derive(Clone)]
+struct Upstream {
+    pool: ChannelPool,
+    circuit_breaker: CircuitBreaker,
+}
+
+/// Why the provider can not handle requests, and routes it can not handle requests for.
+#[derive(Debug, Default)]
+struct Health {
+    problems: Vec<String>,
+    degraded: Vec<String>,
+}
+
+/// How a request is sent by `execute_with_resilience`.
+struct Call {
+    /// The number of requests it counts as for the rate limit.
+    cost: u32,
+    /// Whether it can be sent again when an attempt fails.
+    idempotent: bool,
+    /// Streams are allowed to outlive a single request, their deadline is not sent along.
+    stream: bool,
 }
 
 impl DataAPIProvider {
     fn new(config: HashMap<String, String>) -> Self {
+        let jaws_tokens = Cache::builder()
+            .time_to_live(jaws_token_ttl(&config))
+            .build();
+
+        let cursors = CursorStore::new(
+            Duration::from_secs(config_value(
+                &config,
+                "cursor-idle-timeout-seconds",
+                DEFAULT_CURSOR_IDLE_TIMEOUT_SECONDS,
+            )),
+            config_value(&config, "max-open-cursors", DEFAULT_MAX_OPEN_CURSORS),
+        );
+
+        let rate_limiter = RateLimiter::new(
+            config_value(
+                &config,
+                "rate-limit-requests-per-second",
+                DEFAULT_RATE_LIMIT_REQUESTS_PER_SECOND,
+            ),
+            config_value(&config, "rate-limit-burst", DEFAULT_RATE_LIMIT_BURST),
+            config_value(
+                &config,
+                "max-in-flight-requests",
+                DEFAULT_MAX_IN_FLIGHT_REQUESTS,
+            ),
+        );
+
         DataAPIProvider {
-            static_config: config,
+            key_set: Arc::new(std::sync::RwLock::new(parse_key_set(&config))),
+            static_config: Arc::new(std::sync::RwLock::new(Arc::new(config))),
             wrpc_client: Arc::new(RwLock::new(None)),
+            routing_table: Arc::new(RwLock::new(None)),
+            channel_pools: Arc::new(RwLock::new(HashMap::new())),
+            jaws_tokens,
+            cursors,
+            metrics: Metrics::new(),
+            rate_limiter,
         }
     }
 
@@ -72,36 +190,371 @@ impl DataAPIProvider {
         serve_provider_exports(&wrpc_client, provider, shutdown, bindings::serve).await
     }
 
-    fn data_api_address(&self) -> String {
+    fn config(&self) -> Arc<HashMap<String, String>> {
         self.static_config
+            .read()
+            .expect("config lock is not poisoned")
+            .clone()
+    }
+
+    fn key_set(&self) -> anyhow::Result<Arc<KeySet>> {
+        self.key_set
+            .read()
+            .expect("key set lock is not poisoned")
+            .clone()
+            .map_err(anyhow::Error::msg)
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        let config = self.config();
+
+        RetryPolicy {
+            max_attempts: config_value(&config, "retry-max-attempts", DEFAULT_RETRY_MAX_ATTEMPTS),
+            base_delay: Duration::from_millis(config_value(
+                &config,
+                "retry-base-delay-ms",
+                DEFAULT_RETRY_BASE_DELAY_MS,
+            )),
+            max_delay: Duration::from_millis(config_value(
+                &config,
+                "retry-max-delay-ms",
+                DEFAULT_RETRY_MAX_DELAY_MS,
+            )),
+        }
+    }
+
+    fn data_api_address(&self) -> String {
+        self.config()
             .get("data-api-address")
             .unwrap_or(&DEFAULT_DATA_API_ADDRESS.to_string())
             .to_string()
     }
 
-    async fn inner_request(
-        &self,
-        _ctx: Option<Context>,
-        helper_context: HelperContext,
-        query: String,
-        variables: String,
-    ) -> anyhow::Result<String> {
-        let mut client = DataApiClient::connect(self.data_api_address())
+    async fn routing_table(&self) -> anyhow::Result<RoutingTable> {
+        let maybe_table = {
+            let read_guard = self.routing_table.read().await;
+            read_guard.clone()
+        };
+
+        if let Some(table) = maybe_table {
+            return Ok(table);
+        }
+
+        let mut write_guard = self.routing_table.write().await;
+
+        if let Some(table) = write_guard.as_ref() {
+            return Ok(table.clone());
+        }
+
+        let table = if let Some(routes) = self.config().get("data-api-routes") {
+            RoutingTable::parse(self.data_api_address(), routes)?
+        } else if let Some(secret) = self.config().get("data-api-routes-secret") {
+            let routes = self
+                .get_secret(secret)
+                .await?
+                .context("data-api routes secret not found")?;
+            RoutingTable::parse(self.data_api_address(), &routes)?
+        } else {
+            RoutingTable::new(self.data_api_address(), HashMap::new())
+        };
+
+        *write_guard = Some(table.clone());
+        Ok(table)
+    }
+
+    async fn upstream(&self, application_id: &str) -> anyhow::Result<Upstream> {
+        let address = self
+            .routing_table()
             .await?
-            .max_decoding_message_size(REQUEST_SIZE_LIMIT);
-        let data_api_context = data_grpc::Context {
-            application_id: helper_context.application_id.clone(),
-            jwt: helper_context.jwt.unwrap_or_default(),
+            .address(application_id)
+            .to_string();
+
+        self.address_upstream(address).await
+    }
+
+    async fn address_upstream(&self, address: String) -> anyhow::Result<Upstream> {
+        let maybe_upstream = {
+            let read_guard = self.channel_pools.read().await;
+            read_guard.get(&address).cloned()
         };
-        let data_api_request = DataApiRequest {
-            query,
-            variables,
-            context: Some(data_api_context),
+
+        if let Some(upstream) = maybe_upstream {
+            return Ok(upstream);
+        }
+
+        // NOTE:
+        // Read before locking the pools, it locks the routing table which is locked first when
+        // the config is updated
+        let tls = self.data_api_tls(&address).await?;
+        let mut write_guard = self.channel_pools.write().await;
+
+        // another request might have created the pool while we were waiting for the lock
+        if let Some(upstream) = write_guard.get(&address) {
+            return Ok(upstream.clone());
+        }
+
+        let pool = ChannelPool::new(
+            &address,
+            config_value(
+                &self.config(),
+                "data-api-pool-size",
+                DEFAULT_DATA_API_POOL_SIZE,
+            ),
+            Duration::from_secs(config_value(
+                &self.config(),
+                "data-api-keep-alive-seconds",
+                DEFAULT_DATA_API_KEEP_ALIVE_SECONDS,
+            )),
+            tls,
+        )
+        .context("failed to create data-api channel pool")?;
+
+        info!(
+            "created data-api channel pool for {address} with {} channels",
+            pool.size()
+        );
+
+        let upstream = Upstream {
+            pool,
+            circuit_breaker: self.circuit_breaker(),
+        };
+        write_guard.insert(address, upstream.clone());
+        Ok(upstream)
+    }
+
+    fn circuit_breaker(&self) -> CircuitBreaker {
+        let config = self.config();
+
+        CircuitBreaker::new(
+            config_value(
+                &config,
+                "circuit-breaker-failure-threshold",
+                DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+            ),
+            Duration::from_millis(config_value(
+                &config,
+                "circuit-breaker-reset-timeout-ms",
+                DEFAULT_CIRCUIT_BREAKER_RESET_TIMEOUT_MS,
+            )),
+        )
+    }
+
+    /// TLS is used for `https` addresses, or when any of the certificates is configured.
+    ///
+    /// `data-api-tls-domain` is the domain of the default address, routes set their own.
+    async fn data_api_tls(&self, address: &str) -> anyhow::Result<Option<ClientTlsConfig>> {
+        let domain = match self.routing_table().await?.tls_domain(address) {
+            Some(domain) => Some(domain.to_string()),
+            None if address == self.data_api_address() => {
+                self.config().get("data-api-tls-domain").cloned()
+            }
+            None => None,
+        };
+        let settings = TlsSettings {
+            ca: self.tls_pem("ca").await?,
+            cert: self.tls_pem("cert").await?,
+            key: self.tls_pem("key").await?,
+            domain,
+        };
+
+        if !address.starts_with("https://") && settings.ca.is_none() && settings.cert.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(settings.client_tls_config()?))
+    }
+
+    /// Reads a PEM from the file in `data-api-tls-{name}-file`, or from the key-vault secret
+    /// in `data-api-tls-{name}-secret`.
+    async fn tls_pem(&self, name: &str) -> anyhow::Result<Option<String>> {
+        if let Some(path) = self.config().get(&format!("data-api-tls-{name}-file")) {
+            let pem = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("failed to read data-api tls {name} from {path}"))?;
+            return Ok(Some(pem));
+        }
+
+        if let Some(secret) = self.config().get(&format!("data-api-tls-{name}-secret")) {
+            let pem = self
+                .get_secret(secret)
+                .await?
+                .with_context(|| format!("data-api tls {name} secret not found"))?;
+            return Ok(Some(pem));
+        }
+
+        Ok(None)
+    }
+
+    /// A client for the data-api of the application, with the circuit breaker of that data-api.
+    async fn data_api_client(
+        &self,
+        application_id: &str,
+    ) -> anyhow::Result<(DataApiClient<Channel>, CircuitBreaker)> {
+        let config = self.config();
+        let upstream = self.upstream(application_id).await?;
+
+        // NOTE:
+        // Every encoding is accepted, the data-api picks the one it compresses responses with
+        let client = DataApiClient::new(upstream.pool.channel())
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd)
+            .max_decoding_message_size(config_value(
+                &config,
+                "data-api-max-decoding-message-size",
+                DEFAULT_MAX_DECODING_MESSAGE_SIZE,
+            ))
+            .max_encoding_message_size(config_value(
+                &config,
+                "data-api-max-encoding-message-size",
+                DEFAULT_MAX_ENCODING_MESSAGE_SIZE,
+            ));
+
+        let client = match request_compression(&config) {
+            Some(encoding) => client.send_compressed(encoding),
+            None => client,
         };
+        Ok((client, upstream.circuit_breaker))
+    }
+
+    /// Checks the data-api of every route and the active jaws secret concurrently, all before
+    /// the same deadline.
+    ///
+    /// Every data-api has to report serving through the gRPC health-check protocol. Only the
+    /// default data-api and the jaws secret are needed by every application, other routes that
+    /// are down are reported as degraded.
+    async fn health(&self) -> Health {
+        let deadline = Instant::now()
+            + Duration::from_millis(config_value(
+                &self.config(),
+                "health-check-timeout-ms",
+                DEFAULT_HEALTH_CHECK_TIMEOUT_MS,
+            ));
+        let mut health = Health::default();
 
-        let token = self.generate_jaws(helper_context.application_id).await?;
+        let data_apis = async {
+            let routing_table = self.routing_table().await?;
+            let addresses = routing_table.addresses();
+            let results = futures::future::join_all(
+                addresses
+                    .iter()
+                    .map(|address| self.check_data_api(address, deadline)),
+            )
+            .await;
 
-        let mut request = tonic::Request::new(data_api_request);
+            anyhow::Ok(
+                addresses
+                    .into_iter()
+                    .map(String::from)
+                    .zip(results)
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let jaws_secret = async {
+            let key_set = self.key_set()?;
+            self.get_jaws_secret(&key_set.active.source).await
+        };
+        let (data_apis, jaws_secret) = tokio::join!(
+            tokio::time::timeout_at(deadline.into(), data_apis),
+            tokio::time::timeout_at(deadline.into(), jaws_secret),
+        );
+
+        match data_apis {
+            Ok(Ok(results)) => {
+                // the default address is listed first
+                for (index, (address, result)) in results.into_iter().enumerate() {
+                    let Err(e) = result else {
+                        continue;
+                    };
+                    let problem = format!("data-api {address} is not healthy: {e:#}");
+                    match index {
+                        0 => health.problems.push(problem),
+                        _ => health.degraded.push(problem),
+                    }
+                }
+            }
+            Ok(Err(e)) => health
+                .problems
+                .push(format!("failed to load the data-api routes: {e:#}")),
+            Err(_) => health.problems.push(String::from(
+                "failed to load the data-api routes: timed out",
+            )),
+        }
+
+        match jaws_secret {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => health
+                .problems
+                .push(format!("jaws secret is not retrievable: {e:#}")),
+            Err(_) => health
+                .problems
+                .push(String::from("jaws secret is not retrievable: timed out")),
+        }
+
+        health
+    }
+
+    async fn check_data_api(&self, address: &str, deadline: Instant) -> anyhow::Result<()> {
+        let mut request = tonic::Request::new(health_grpc::HealthCheckRequest {
+            service: String::from(DATA_API_SERVICE_NAME),
+        });
+        request.set_timeout(deadline.saturating_duration_since(Instant::now()));
+
+        let check = async {
+            let upstream = self.address_upstream(address.to_string()).await?;
+            HealthClient::new(upstream.pool.channel())
+                .check(request)
+                .await
+                .map_err(|status| anyhow::anyhow!("{}: {}", status.code(), status.message()))
+        };
+        let response = tokio::time::timeout_at(deadline.into(), check)
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out"))??;
+
+        match response.into_inner().status() {
+            ServingStatus::Serving => Ok(()),
+            status => anyhow::bail!("status is {}", status.as_str_name()),
+        }
+    }
+
+    /// The moment a request made with `helper_context` has to be finished.
+    fn request_deadline(&self, helper_context: &HelperContext) -> Instant {
+        let timeout = match helper_context.timeout_ms {
+            Some(timeout_ms) => Duration::from_millis(timeout_ms.into()),
+            None => self.default_request_timeout(),
+        };
+
+        Instant::now() + timeout
+    }
+
+    fn default_request_timeout(&self) -> Duration {
+        Duration::from_millis(config_value(
+            &self.config(),
+            "request-timeout-ms",
+            DEFAULT_REQUEST_TIMEOUT_MS,
+        ))
+    }
+
+    /// Builds a request authorized for the application of `helper_context`.
+    ///
+    /// The deadline is left out for streams, which are allowed to outlive a single request.
+    async fn authorized_request<T>(
+        &self,
+        ctx: &Option<Context>,
+        helper_context: &HelperContext,
+        message: T,
+        deadline: Option<Instant>,
+        key: KeyRole,
+    ) -> anyhow::Result<tonic::Request<T>> {
+        let token = self
+            .generate_jaws(helper_context.application_id.clone(), key)
+            .await?;
+
+        let mut request = tonic::Request::new(message);
+        if let Some(deadline) = deadline {
+            // NOTE:
+            // Sent as the `grpc-timeout` header, so the data-api can stop working on the request too
+            request.set_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
 
         let metadata = request.metadata_mut();
         metadata.insert(
@@ -111,23 +564,373 @@ impl DataAPIProvider {
                 .expect("valid bearer header"),
         );
 
-        info!("sending request");
+        for (key, value) in correlation_metadata(ctx, helper_context) {
+            match value.parse() {
+                Ok(value) => {
+                    metadata.insert(key, value);
+                }
+                Err(_) => warn!("not forwarding {key}, the value is not valid gRPC metadata"),
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Sends the request built by `send`, retrying transient failures of idempotent requests
+    /// until `deadline` has passed.
+    ///
+    /// While rotating keys the data-api might not accept the active jaws key yet, so an
+    /// idempotent request whose jaws token was rejected is sent once more signed with the
+    /// previous key, when one is configured.
+    ///
+    /// When the invocation of the component is cancelled this future is dropped, which cancels
+    /// the in-flight gRPC call as well.
+    async fn execute_with_retry<T, F, Fut>(
+        &self,
+        circuit_breaker: &CircuitBreaker,
+        idempotent: bool,
+        deadline: Instant,
+        mut send: F,
+    ) -> Result<T, DataApiError>
+    where
+        F: FnMut(KeyRole) -> Fut,
+        Fut: Future<Output = Result<T, AttemptError>>,
+    {
+        let attempts = async {
+            match self
+                .execute_attempts(circuit_breaker, idempotent, KeyRole::Active, &mut send)
+                .await
+            {
+                Err(AttemptError::JawsRejected(e))
+                    if idempotent && self.has_previous_jaws_key() =>
+                {
+                    warn!("data-api rejected the active jaws key ({e:?}), retrying with the previous key");
+                    self.execute_attempts(circuit_breaker, idempotent, KeyRole::Previous, &mut send)
+                        .await
+                }
+                result => result,
+            }
+        };
+        match tokio::time::timeout_at(deadline.into(), attempts).await {
+            Ok(result) => result.map_err(AttemptError::into_error),
+            Err(_) => Err(DataApiError::Timeout(String::from(
+                "the data-api did not respond before the deadline",
+            ))),
+        }
+    }
+
+    async fn execute_attempts<T, F, Fut>(
+        &self,
+        circuit_breaker: &CircuitBreaker,
+        idempotent: bool,
+        key: KeyRole,
+        send: &mut F,
+    ) -> Result<T, AttemptError>
+    where
+        F: FnMut(KeyRole) -> Fut,
+        Fut: Future<Output = Result<T, AttemptError>>,
+    {
+        let retry_policy = self.retry_policy();
+        let mut attempt = 0;
+        loop {
+            if !circuit_breaker.allow() {
+                return Err(DataApiError::Connection(String::from(
+                    "circuit breaker is open, the data-api is unavailable",
+                ))
+                .into());
+            }
+
+            match send(key).await {
+                Err(e) if retry::is_transient(e.error()) => {
+                    circuit_breaker.record_failure();
 
-        match client.execute(request).await {
-            Ok(response) => {
-                let response_data = response.into_inner();
-                match Status::try_from(response_data.status) {
-                    Ok(Status::Ok) => Ok(response_data.result),
-                    _ => Err(anyhow::anyhow!(response_data.result)),
+                    attempt += 1;
+                    if !idempotent || attempt >= retry_policy.max_attempts {
+                        return Err(e);
+                    }
+
+                    let delay = retry_policy.backoff(attempt);
+                    warn!("data-api request failed ({e:?}), retrying in {delay:?}");
+                    tokio::time::sleep(delay).await;
+                }
+                result => {
+                    circuit_breaker.record_success();
+                    return result;
                 }
             }
-            Err(e) => Err(e.into()),
         }
     }
 
-    async fn generate_jaws(&self, application_id: String) -> anyhow::Result<String> {
+    /// Sends a request of the application of `helper_context` to its data-api, through the
+    /// rate limit of the application, the circuit breaker of the data-api and the retries.
+    ///
+    /// `send` is called for every attempt with the client and `message` in a request signed for
+    /// that attempt.
+    async fn execute_with_resilience<M, T, F, Fut>(
+        &self,
+        ctx: &Option<Context>,
+        helper_context: &HelperContext,
+        call: Call,
+        message: M,
+        send: F,
+    ) -> Result<T, DataApiError>
+    where
+        M: Clone,
+        F: Fn(DataApiClient<Channel>, tonic::Request<M>) -> Fut,
+        Fut: Future<Output = Result<T, AttemptError>>,
+    {
+        let deadline = self.request_deadline(helper_context);
+        // NOTE:
+        // Only opening a stream counts as in flight, reading the chunks does not
+        let _permit = self
+            .rate_limiter
+            .acquire(&helper_context.application_id, call.cost)
+            .await?;
+        let (client, circuit_breaker) =
+            self.data_api_client(&helper_context.application_id).await?;
+
+        // NOTE:
+        // Borrowed, so every attempt can build its request from them
+        let (message, client, send) = (&message, &client, &send);
+
+        self.execute_with_retry(
+            &circuit_breaker,
+            call.idempotent,
+            deadline,
+            move |key| async move {
+                let request = self
+                    .authorized_request(
+                        ctx,
+                        helper_context,
+                        message.clone(),
+                        (!call.stream).then_some(deadline),
+                        key,
+                    )
+                    .await?;
+                send(client.clone(), request).await
+            },
+        )
+        .await
+    }
+
+    async fn inner_request(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        query: String,
+        variables: String,
+    ) -> Result<String, DataApiError> {
+        let idempotent = retry::is_idempotent(&query);
+        let data_api_request = DataApiRequest {
+            query,
+            variables,
+            context: Some(data_api_context(&helper_context)),
+        };
+
+        self.execute_with_resilience(
+            &ctx,
+            &helper_context,
+            Call {
+                cost: 1,
+                idempotent,
+                stream: false,
+            },
+            data_api_request,
+            |mut client, request| async move {
+                info!("sending request");
+
+                let response = client.execute(request).await?;
+                Ok(into_result(response.into_inner())?)
+            },
+        )
+        .await
+    }
+
+    async fn inner_request_batch(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        queries: Vec<(String, String)>,
+    ) -> Result<Vec<Result<String, DataApiError>>, DataApiError> {
+        // every query counts as a request
+        let cost = u32::try_from(queries.len()).unwrap_or(u32::MAX);
+        let idempotent = queries.iter().all(|(query, _)| retry::is_idempotent(query));
+        let data_api_request = DataApiBatchRequest {
+            queries: queries
+                .into_iter()
+                .map(|(query, variables)| Query { query, variables })
+                .collect(),
+            context: Some(data_api_context(&helper_context)),
+        };
+
+        self.execute_with_resilience(
+            &ctx,
+            &helper_context,
+            Call {
+                cost,
+                idempotent,
+                stream: false,
+            },
+            data_api_request,
+            |mut client, request| async move {
+                info!(
+                    "sending batch request with {} queries",
+                    request.get_ref().queries.len()
+                );
+
+                let response = client.execute_batch(request).await?;
+                Ok(response
+                    .into_inner()
+                    .results
+                    .into_iter()
+                    .map(into_result)
+                    .collect())
+            },
+        )
+        .await
+    }
+
+    async fn inner_request_stream(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        query: String,
+        variables: String,
+    ) -> Result<ResourceOwn<Cursor>, DataApiError> {
+        let idempotent = retry::is_idempotent(&query);
+        let data_api_request = DataApiRequest {
+            query,
+            variables,
+            context: Some(data_api_context(&helper_context)),
+        };
+
+        let stream = self
+            .execute_with_resilience(
+                &ctx,
+                &helper_context,
+                Call {
+                    cost: 1,
+                    idempotent,
+                    stream: true,
+                },
+                data_api_request,
+                |mut client, request| async move {
+                    info!("sending stream request");
+
+                    let response = client.execute_stream(request).await?;
+                    Ok(response.into_inner())
+                },
+            )
+            .await?;
+
+        let component = ctx.and_then(|ctx| ctx.component);
+        let handle = self
+            .cursors
+            .insert(helper_context.application_id, component, stream)
+            .await;
+        Ok(ResourceOwn::new(handle))
+    }
+
+    async fn inner_decrypt_configurations(
+        &self,
+        helper_context: HelperContext,
+    ) -> Result<Vec<String>, DataApiError> {
+        let Some(configurations) = helper_context.encrypted_configurations else {
+            return Ok(Vec::new());
+        };
+
+        let key_name = self
+            .config()
+            .get("configurations-key")
+            .cloned()
+            .unwrap_or(String::from("ACTIONS_WASM_CONFIGURATIONS_KEY"));
+
+        let key = self
+            .get_secret(&key_name)
+            .await?
+            .ok_or_else(|| DataApiError::Internal(String::from("configurations key not found")))?;
+
+        configurations
+            .iter()
+            .enumerate()
+            .map(|(index, configuration)| {
+                configurations::decrypt(&key, &helper_context.application_id, configuration)
+                    .map_err(|e| {
+                        DataApiError::Internal(format!(
+                            "failed to decrypt configuration {index}: {e:#}"
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    async fn inner_next(
+        &self,
+        ctx: Option<Context>,
+        handle: &Bytes,
+    ) -> Result<Option<String>, DataApiError> {
+        let Some(cursor) = self.cursors.get(handle).await else {
+            return Err(DataApiError::Internal(String::from(
+                "cursor does not exist or has expired",
+            )));
+        };
+
+        // NOTE:
+        // `next` is not called with a helper-context, so the cursor is bound to the component
+        // that opened it for its application
+        let component = ctx.as_ref().and_then(|ctx| ctx.component.as_deref());
+        if cursor.component.as_deref() != component {
+            warn!(
+                application_id = cursor.application_id,
+                ?component,
+                "denied reading a cursor opened by another component"
+            );
+            return Err(DataApiError::Unauthorized(String::from(
+                "the cursor was opened by another component",
+            )));
+        }
+
+        let mut stream = cursor.stream.lock().await;
+        let message = tokio::time::timeout(self.default_request_timeout(), stream.message()).await;
+
+        let result = match message {
+            Ok(Ok(Some(chunk))) => into_result(chunk).map(Some),
+            Ok(Ok(None)) => Ok(None),
+            Ok(Err(status)) => Err(status.into()),
+            Err(_) => Err(DataApiError::Timeout(String::from(
+                "the data-api did not send the next chunk in time",
+            ))),
+        };
+
+        // NOTE:
+        // Exhausted and failed streams are closed right away instead of when they expire
+        if !matches!(result, Ok(Some(_))) {
+            self.cursors.remove(handle).await;
+        }
+
+        result
+    }
+
+    fn has_previous_jaws_key(&self) -> bool {
+        self.key_set()
+            .is_ok_and(|key_set| key_set.previous.is_some())
+    }
+
+    async fn generate_jaws(&self, application_id: String, key: KeyRole) -> anyhow::Result<String> {
+        // NOTE:
+        // Concurrent requests for the same application wait for a single token to be minted
+        self.jaws_tokens
+            .try_get_with(
+                (key, application_id.clone()),
+                self.mint_jaws(application_id, key),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:#}"))
+    }
+
+    async fn mint_jaws(&self, application_id: String, key: KeyRole) -> anyhow::Result<String> {
         let jaws_issuer = self
-            .static_config
+            .config()
             .get("jaws-issuer")
             .cloned()
             .unwrap_or(String::from("actions-wasm"));
@@ -135,24 +938,51 @@ impl DataAPIProvider {
         let issued_at = jaws_rs::jsonwebtoken::get_current_timestamp();
         let claims = jaws_rs::Claims::new(jaws_issuer, application_id, issued_at, random_string());
 
-        let secret_key = self.get_jaws_secret().await?;
-        Ok(jaws_rs::encode(&claims, &secret_key)?)
+        let key_set = self.key_set()?;
+        let signing_key = key_set
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!("no previous jaws key is configured"))?;
+
+        let secret_key = self.get_jaws_secret(&signing_key.source).await?;
+        signing_key::encode(&claims, signing_key.kid.clone(), &secret_key)
     }
 
-    async fn get_jaws_secret(&self) -> anyhow::Result<String> {
+    async fn get_jaws_secret(&self, source: &SecretSource) -> anyhow::Result<String> {
+        match source {
+            SecretSource::KeyVault(name) => self
+                .get_secret(name)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Jaws secret not found")),
+            SecretSource::Env(name) => std::env::var(name)
+                .with_context(|| format!("failed to read the jaws secret from ${name}")),
+            SecretSource::File(path) => {
+                let secret = tokio::fs::read_to_string(path).await.with_context(|| {
+                    format!("failed to read the jaws secret from {}", path.display())
+                })?;
+                Ok(secret.trim_end().to_string())
+            }
+        }
+    }
+
+    async fn get_secret(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let wrpc_client = self.key_vault_client().await?;
+        Ok(key_vault::get_secret(&wrpc_client, None, key).await??)
+    }
+
+    async fn key_vault_client(&self) -> anyhow::Result<WrpcClient> {
         let maybe_client = {
             let read_guard = self.wrpc_client.read().await;
             read_guard.clone()
         };
 
         if let Some(wrpc_client) = maybe_client {
-            return self.inner_get_jaws_secret(&wrpc_client).await;
+            return Ok(wrpc_client);
         }
 
         info!("making new connection to key-vault");
 
         let key_vault_target = self
-            .static_config
+            .config()
             .get("key-vault-target")
             .cloned()
             .unwrap_or(String::from("key-vault"));
@@ -167,47 +997,818 @@ impl DataAPIProvider {
             *write_guard = Some(wrpc_client.clone());
         }
 
-        self.inner_get_jaws_secret(&wrpc_client).await
+        Ok(wrpc_client)
     }
+}
 
-    async fn inner_get_jaws_secret(&self, wrpc_client: &WrpcClient) -> anyhow::Result<String> {
-        let jaws_secret_key = self
-            .static_config
-            .get("jaws-secret-key")
-            .cloned()
-            .unwrap_or(String::from("ACTIONS_WASM_DATA_API_SECRET"));
+fn data_api_context(helper_context: &HelperContext) -> data_grpc::Context {
+    data_grpc::Context {
+        application_id: helper_context.application_id.clone(),
+        jwt: helper_context.jwt.clone().unwrap_or_default(),
+    }
+}
+
+/// Metadata that lets the data-api correlate its logs and traces with the action that sent the
+/// request.
+///
+/// The trace context of the current span is preferred, so the data-api shows up as a child of
+/// the provider in traces. Without an exporter that span has no trace context, in that case the
+/// trace context of the invocation is forwarded as is.
+fn correlation_metadata(
+    ctx: &Option<Context>,
+    helper_context: &HelperContext,
+) -> Vec<(&'static str, String)> {
+    let mut trace_context = TraceContextInjector::default_with_span();
+    if !trace_context.contains_key("traceparent") {
+        if let Some(ctx) = ctx {
+            trace_context = TraceContextInjector::new(ctx.tracing.clone().into_iter().collect());
+        }
+    }
+
+    let mut metadata = vec![
+        ("x-application-id", helper_context.application_id.clone()),
+        ("x-action-id", helper_context.action_id.clone()),
+        ("x-log-id", helper_context.log_id.clone()),
+    ];
+    for key in ["traceparent", "tracestate"] {
+        if let Some(value) = trace_context.get(key) {
+            metadata.push((key, value.clone()));
+        }
+    }
+
+    metadata
+}
+
+fn into_result(data_api_result: DataApiResult) -> Result<String, DataApiError> {
+    match Status::try_from(data_api_result.status) {
+        Ok(Status::Ok) => Ok(data_api_result.result),
+        _ => Err(error::from_error_result(&data_api_result.result)),
+    }
+}
+
+fn config_value<T: FromStr>(config: &HashMap<String, String>, key: &str, default: T) -> T {
+    match config.get(key) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            warn!("invalid value for {key}: {value}, using the default");
+            default
+        }),
+        None => default,
+    }
+}
 
-        match key_vault::get_secret(wrpc_client, None, &jaws_secret_key).await {
-            Ok(Some(secret)) => Ok(secret),
-            Ok(None) => Err(anyhow::anyhow!("Jaws secret not found")),
-            Err(e) => Err(e),
+/// Requests are only compressed when configured, not every data-api accepts compressed requests.
+fn request_compression(config: &HashMap<String, String>) -> Option<CompressionEncoding> {
+    match config.get("data-api-compression").map(String::as_str) {
+        None | Some("none") => None,
+        Some("gzip") => Some(CompressionEncoding::Gzip),
+        Some("zstd") => Some(CompressionEncoding::Zstd),
+        Some(value) => {
+            warn!("invalid value for data-api-compression: {value}, not compressing requests");
+            None
         }
     }
 }
 
+fn parse_key_set(config: &HashMap<String, String>) -> Result<Arc<KeySet>, String> {
+    KeySet::from_config(config)
+        .map(Arc::new)
+        .map_err(|e| format!("{e:#}"))
+}
+
+fn jaws_token_ttl(config: &HashMap<String, String>) -> Duration {
+    let lifetime = config_value(
+        config,
+        "jaws-token-lifetime-seconds",
+        DEFAULT_JAWS_TOKEN_LIFETIME_SECONDS,
+    );
+    let refresh_margin = config_value(
+        config,
+        "jaws-token-refresh-margin-seconds",
+        DEFAULT_JAWS_TOKEN_REFRESH_MARGIN_SECONDS,
+    );
+
+    Duration::from_secs(lifetime.saturating_sub(refresh_margin))
+}
+
 fn random_string() -> String {
     let mut rng = rand::rng();
-    Alphanumeric::default().sample_string(&mut rng, 16)
+    Alphanumeric.sample_string(&mut rng, 16)
 }
 
-impl Provider for DataAPIProvider {}
+impl Provider for DataAPIProvider {
+    /// Called by the host at regular intervals, wadm does not route to an unhealthy instance.
+    async fn health_request(
+        &self,
+        _request: &HealthCheckRequest,
+    ) -> anyhow::Result<HealthCheckResponse> {
+        let health = self.health().await;
+        if !health.problems.is_empty() {
+            warn!(
+                "data-api provider is not healthy: {}",
+                health.problems.join(", ")
+            );
+        }
+        if !health.degraded.is_empty() {
+            warn!(
+                "data-api provider is degraded: {}",
+                health.degraded.join(", ")
+            );
+        }
+
+        let messages = health.problems.iter().chain(&health.degraded);
+        let message = messages.cloned().collect::<Vec<_>>().join(", ");
+        Ok(HealthCheckResponse {
+            healthy: health.problems.is_empty(),
+            message: (!message.is_empty()).then_some(message),
+        })
+    }
+
+    /// Swaps the config and drops everything that was derived from the old one: the routing
+    /// table, channel pools, circuit breakers, key-vault client and jaws tokens are created
+    /// again on the next request. In-flight requests finish with what they started with.
+    ///
+    /// The jaws token lifetime, cursor and rate limit settings are only read when the provider
+    /// starts.
+    async fn on_config_update(&self, update: impl ProviderConfigUpdate) -> anyhow::Result<()> {
+        info!("updating the data-api provider config");
+
+        // NOTE:
+        // Holding every lock while swapping, so no request combines the new config with a
+        // client that was created for the old one
+        let mut routing_table = self.routing_table.write().await;
+        let mut channel_pools = self.channel_pools.write().await;
+        let mut wrpc_client = self.wrpc_client.write().await;
+
+        let config = update.get_values().clone();
+        *self.key_set.write().expect("key set lock is not poisoned") = parse_key_set(&config);
+        *self
+            .static_config
+            .write()
+            .expect("config lock is not poisoned") = Arc::new(config);
+
+        *routing_table = None;
+        // NOTE:
+        // The circuit breakers are kept with the pools, an endpoint is usually swapped because
+        // it is down so an open breaker must not reject the new one
+        channel_pools.clear();
+        *wrpc_client = None;
+        self.jaws_tokens.invalidate_all();
+
+        Ok(())
+    }
+}
 
 impl Handler<Option<Context>> for DataAPIProvider {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(action_id = %helper_context.action_id, log_id = %helper_context.log_id)
+    )]
     async fn request(
         &self,
         ctx: Option<Context>,
         helper_context: HelperContext,
         query: String,
         variables: String,
-    ) -> anyhow::Result<Result<String, String>> {
+    ) -> anyhow::Result<Result<String, DataApiError>> {
+        propagate_trace_for_ctx!(ctx);
         info!("Hello to your logs from DataAPI provider");
 
-        match self
+        let started = Instant::now();
+        let application_id = helper_context.application_id.clone();
+        let request_size = query.len() + variables.len();
+
+        let result = self
             .inner_request(ctx, helper_context, query, variables)
-            .await
-        {
-            Ok(x) => Ok(Ok(x)),
-            Err(e) => Ok(Err(e.to_string())),
+            .await;
+
+        self.metrics.record(
+            "request",
+            &application_id,
+            started,
+            request_size,
+            result.as_ref().map(|result| Some(result.len())),
+        );
+        Ok(result)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(action_id = %helper_context.action_id, log_id = %helper_context.log_id)
+    )]
+    async fn request_batch(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        queries: Vec<(String, String)>,
+    ) -> anyhow::Result<Result<Vec<Result<String, DataApiError>>, DataApiError>> {
+        propagate_trace_for_ctx!(ctx);
+
+        let started = Instant::now();
+        let application_id = helper_context.application_id.clone();
+        let request_size = queries
+            .iter()
+            .map(|(query, variables)| query.len() + variables.len())
+            .sum();
+
+        let result = self.inner_request_batch(ctx, helper_context, queries).await;
+
+        self.metrics.record(
+            "request-batch",
+            &application_id,
+            started,
+            request_size,
+            result.as_ref().map(|results| {
+                Some(
+                    results
+                        .iter()
+                        .filter_map(|result| result.as_ref().ok())
+                        .map(String::len)
+                        .sum(),
+                )
+            }),
+        );
+        Ok(result)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(action_id = %helper_context.action_id, log_id = %helper_context.log_id)
+    )]
+    async fn request_stream(
+        &self,
+        ctx: Option<Context>,
+        helper_context: HelperContext,
+        query: String,
+        variables: String,
+    ) -> anyhow::Result<Result<ResourceOwn<Cursor>, DataApiError>> {
+        propagate_trace_for_ctx!(ctx);
+
+        let started = Instant::now();
+        let application_id = helper_context.application_id.clone();
+        let request_size = query.len() + variables.len();
+
+        let result = self
+            .inner_request_stream(ctx, helper_context, query, variables)
+            .await;
+
+        // NOTE:
+        // Only opening the stream is measured, the chunks are read later through the cursor so
+        // the size of the response is not known
+        self.metrics.record(
+            "request-stream",
+            &application_id,
+            started,
+            request_size,
+            result.as_ref().map(|_| None),
+        );
+        Ok(result)
+    }
+
+    async fn decrypt_configurations(
+        &self,
+        _ctx: Option<Context>,
+        helper_context: HelperContext,
+    ) -> anyhow::Result<Result<Vec<String>, DataApiError>> {
+        Ok(self.inner_decrypt_configurations(helper_context).await)
+    }
+}
+
+impl HandlerCursor<Option<Context>> for DataAPIProvider {
+    async fn next(
+        &self,
+        ctx: Option<Context>,
+        cursor: ResourceBorrow<Cursor>,
+    ) -> anyhow::Result<Result<Option<String>, DataApiError>> {
+        Ok(self.inner_next(ctx, cursor.as_ref()).await)
+    }
+}
+
+#[test]
+fn test_jaws_token_ttl() {
+    assert_eq!(jaws_token_ttl(&HashMap::new()), Duration::from_secs(270));
+
+    let config = HashMap::from([
+        (
+            String::from("jaws-token-lifetime-seconds"),
+            String::from("120"),
+        ),
+        (
+            String::from("jaws-token-refresh-margin-seconds"),
+            String::from("20"),
+        ),
+    ]);
+    assert_eq!(jaws_token_ttl(&config), Duration::from_secs(100));
+
+    let provider = DataAPIProvider::new(config);
+    assert_eq!(
+        provider.jaws_tokens.policy().time_to_live(),
+        Some(Duration::from_secs(100))
+    );
+
+    // a margin larger than the lifetime disables caching instead of underflowing
+    let config = HashMap::from([(
+        String::from("jaws-token-refresh-margin-seconds"),
+        String::from("600"),
+    )]);
+    assert_eq!(jaws_token_ttl(&config), Duration::ZERO);
+}
+
+#[test]
+fn test_into_result() {
+    let ok = DataApiResult {
+        status: Status::Ok.into(),
+        result: String::from(r#"{"data":{}}"#),
+    };
+    assert_eq!(into_result(ok).unwrap(), r#"{"data":{}}"#);
+
+    let error = DataApiResult {
+        status: Status::Error.into(),
+        result: String::from(r#"{"errors":[{"message":"invalid"}]}"#),
+    };
+    assert!(matches!(
+        into_result(error),
+        Err(DataApiError::Validation(errors)) if errors[0].message == "invalid"
+    ));
+}
+
+#[tokio::test]
+async fn test_execute_with_retry() {
+    let provider = DataAPIProvider::new(HashMap::from([(
+        String::from("retry-base-delay-ms"),
+        String::from("1"),
+    )]));
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    let attempts = std::sync::atomic::AtomicU32::new(0);
+    let send = async |_| match attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+        0 | 1 => Err(DataApiError::Connection(String::from("unavailable")).into()),
+        _ => Ok("done"),
+    };
+
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), true, deadline, send)
+        .await;
+    assert!(matches!(result, Ok("done")));
+    assert_eq!(attempts.into_inner(), 3);
+
+    // mutations are not retried
+    let attempts = std::sync::atomic::AtomicU32::new(0);
+    let send = |_| async {
+        attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Err::<(), _>(DataApiError::Connection(String::from("unavailable")).into())
+    };
+
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), false, deadline, send)
+        .await;
+    assert!(matches!(result, Err(DataApiError::Connection(_))));
+    assert_eq!(attempts.into_inner(), 1);
+}
+
+#[tokio::test]
+async fn test_execute_with_retry_deadline() {
+    let provider = DataAPIProvider::new(HashMap::new());
+    let deadline = Instant::now() + Duration::from_millis(10);
+
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), true, deadline, async |_| {
+            std::future::pending::<Result<(), AttemptError>>().await
+        })
+        .await;
+    assert!(matches!(result, Err(DataApiError::Timeout(_))));
+}
+
+#[tokio::test]
+async fn test_execute_with_retry_previous_key() {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let send = |key| async move {
+        match key {
+            KeyRole::Active => Err(tonic::Status::unauthenticated("invalid jaws").into()),
+            KeyRole::Previous => Ok("done"),
         }
+    };
+
+    // without a previous key the rejection is returned as is
+    let provider = DataAPIProvider::new(HashMap::new());
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), true, deadline, send)
+        .await;
+    assert!(matches!(result, Err(DataApiError::Unauthorized(_))));
+
+    let provider = DataAPIProvider::new(HashMap::from([(
+        String::from("jaws-previous-secret-key"),
+        String::from("ACTIONS_WASM_DATA_API_PREVIOUS_SECRET"),
+    )]));
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), true, deadline, send)
+        .await;
+    assert!(matches!(result, Ok("done")));
+
+    // mutations are never sent twice
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), false, deadline, send)
+        .await;
+    assert!(matches!(result, Err(DataApiError::Unauthorized(_))));
+
+    // the data-api rejecting the jwt of the user is not about the jaws key
+    let send = |key| async move {
+        match key {
+            KeyRole::Active => Err(DataApiError::Unauthorized(String::from("forbidden")).into()),
+            KeyRole::Previous => Ok("done"),
+        }
+    };
+    let result = provider
+        .execute_with_retry(&provider.circuit_breaker(), true, deadline, send)
+        .await;
+    assert!(matches!(result, Err(DataApiError::Unauthorized(_))));
+}
+
+#[cfg(test)]
+async fn start_mock_data_api(data_api: mock_data_api::MockDataApi) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(mock_data_api::serve(listener, data_api));
+    address
+}
+
+// NOTE:
+// Read from a file instead of the environment, which is shared by the tests running in parallel
+#[cfg(test)]
+fn jaws_secret_file() -> &'static std::path::Path {
+    static JAWS_SECRET_FILE: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    JAWS_SECRET_FILE.get_or_init(|| {
+        let path =
+            std::env::temp_dir().join(format!("data-api-test-jaws-secret-{}", std::process::id()));
+        std::fs::write(&path, "secret").unwrap();
+        path
+    })
+}
+
+/// The config of a provider that sends requests to `address`, signed with the test jaws secret.
+#[cfg(test)]
+fn test_config<const N: usize>(address: &str, extra: [(&str, &str); N]) -> HashMap<String, String> {
+    let mut config = HashMap::from([
+        (String::from("data-api-address"), address.to_string()),
+        (String::from("jaws-key-source"), String::from("file")),
+        (
+            String::from("jaws-secret-file"),
+            jaws_secret_file().display().to_string(),
+        ),
+    ]);
+    config.extend(extra.map(|(key, value)| (key.to_string(), value.to_string())));
+    config
+}
+
+#[cfg(test)]
+fn test_context(application_id: &str) -> HelperContext {
+    HelperContext {
+        application_id: application_id.to_string(),
+        action_id: String::from("action"),
+        log_id: String::from("log"),
+        encrypted_configurations: None,
+        jwt: None,
+        timeout_ms: None,
+    }
+}
+
+#[test]
+fn test_correlation_metadata() {
+    let helper_context = test_context("application");
+    let ctx = Context {
+        component: None,
+        tracing: HashMap::from([
+            (
+                String::from("traceparent"),
+                String::from("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"),
+            ),
+            (String::from("baggage"), String::from("ignored")),
+        ]),
+    };
+
+    assert_eq!(
+        correlation_metadata(&Some(ctx), &helper_context),
+        vec![
+            ("x-application-id", String::from("application")),
+            ("x-action-id", String::from("action")),
+            ("x-log-id", String::from("log")),
+            (
+                "traceparent",
+                String::from("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+            ),
+        ]
+    );
+
+    assert_eq!(correlation_metadata(&None, &helper_context).len(), 3);
+}
+
+#[tokio::test]
+async fn test_requests_against_mock_data_api() {
+    let address =
+        start_mock_data_api(mock_data_api::MockDataApi::default().with_chunk_size(8)).await;
+    let provider = DataAPIProvider::new(test_config(&address, []));
+    let helper_context = test_context("application");
+
+    let created = provider
+        .inner_request(
+            None,
+            helper_context.clone(),
+            String::from("mutation($input: UserInput) { createUser(input: $input) { id } }"),
+            String::from(r#"{"input": {"name": "Betty"}}"#),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created, r#"{"createUser":{"id":"1"}}"#);
+
+    let results = provider
+        .inner_request_batch(
+            None,
+            helper_context.clone(),
+            vec![
+                (
+                    String::from("mutation($id: Int!) { deleteUser(id: $id) { id } }"),
+                    String::from(r#"{"id": "2"}"#),
+                ),
+                (
+                    String::from(
+                        "query($where: UserFilterInput) { oneUser(where: $where) { id } }",
+                    ),
+                    String::from(r#"{"where": {"id": {"eq": "1"}}}"#),
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+    assert!(matches!(results[0], Err(DataApiError::Validation(_))));
+    assert_eq!(
+        results[1].as_deref().unwrap(),
+        r#"{"oneUser":{"id":"1","name":"Betty"}}"#
+    );
+
+    let cursor = provider
+        .inner_request_stream(
+            None,
+            helper_context.clone(),
+            String::from("{allUser{results{id}}}"),
+            String::new(),
+        )
+        .await
+        .unwrap();
+
+    let mut result = String::new();
+    while let Some(chunk) = provider.inner_next(None, cursor.as_ref()).await.unwrap() {
+        result.push_str(&chunk);
     }
+    assert_eq!(
+        result,
+        r#"{"allUser":{"results":[{"id":"1","name":"Betty"}],"totalCount":1}}"#
+    );
+
+    // the exhausted stream is closed
+    assert!(matches!(
+        provider.inner_next(None, cursor.as_ref()).await,
+        Err(DataApiError::Internal(_))
+    ));
+
+    let cx = |component: &str| {
+        Some(Context {
+            component: Some(component.to_string()),
+            ..Default::default()
+        })
+    };
+    let cursor = provider
+        .inner_request_stream(
+            cx("action"),
+            helper_context,
+            String::from("{allUser{results{id}}}"),
+            String::new(),
+        )
+        .await
+        .unwrap();
+
+    // other components can not read from the cursor
+    assert!(matches!(
+        provider.inner_next(cx("other"), cursor.as_ref()).await,
+        Err(DataApiError::Unauthorized(_))
+    ));
+    assert!(provider
+        .inner_next(cx("action"), cursor.as_ref())
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_requests_are_routed_per_application() {
+    let address = start_mock_data_api(mock_data_api::MockDataApi::default()).await;
+    let provider = DataAPIProvider::new(test_config(
+        &address,
+        [
+            (
+                "data-api-routes",
+                r#"{"dedicated-*": "http://127.0.0.1:1"}"#,
+            ),
+            ("retry-max-attempts", "1"),
+            ("circuit-breaker-failure-threshold", "1"),
+        ],
+    ));
+    let query = || String::from("{allUser{results{id}}}");
+
+    let result = provider
+        .inner_request(None, test_context("application"), query(), String::new())
+        .await;
+    assert!(result.is_ok());
+
+    // nothing listens on the dedicated cluster
+    let result = provider
+        .inner_request(None, test_context("dedicated-1"), query(), String::new())
+        .await;
+    assert!(matches!(result, Err(DataApiError::Connection(_))));
+
+    assert_eq!(provider.channel_pools.read().await.len(), 2);
+
+    // the breaker of the dedicated cluster is open, the other applications are not affected
+    let result = provider
+        .inner_request(None, test_context("dedicated-2"), query(), String::new())
+        .await;
+    assert!(
+        matches!(result, Err(DataApiError::Connection(message)) if message.contains("circuit breaker"))
+    );
+    let result = provider
+        .inner_request(None, test_context("application"), query(), String::new())
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_on_config_update() {
+    let address = start_mock_data_api(mock_data_api::MockDataApi::default()).await;
+    let config = |address: &str| {
+        test_config(
+            address,
+            [
+                ("retry-max-attempts", "1"),
+                ("circuit-breaker-failure-threshold", "1"),
+                ("circuit-breaker-reset-timeout-ms", "60000"),
+            ],
+        )
+    };
+    let helper_context = test_context("application");
+    let query = || String::from("{allUser{results{id}}}");
+
+    let provider = DataAPIProvider::new(config("http://127.0.0.1:1"));
+    let result = provider
+        .inner_request(None, helper_context.clone(), query(), String::new())
+        .await;
+    assert!(matches!(result, Err(DataApiError::Connection(_))));
+
+    provider.on_config_update(&config(&address)).await.unwrap();
+    assert_eq!(provider.data_api_address(), address);
+    assert!(provider.channel_pools.read().await.is_empty());
+
+    let result = provider
+        .inner_request(None, helper_context.clone(), query(), String::new())
+        .await;
+    assert!(result.is_ok());
+
+    // the breaker of the unreachable endpoint opened, updating the config closes it again
+    provider
+        .on_config_update(&config("http://127.0.0.1:1"))
+        .await
+        .unwrap();
+    let result = provider
+        .inner_request(None, helper_context.clone(), query(), String::new())
+        .await;
+    assert!(
+        matches!(result, Err(DataApiError::Connection(message)) if !message.contains("circuit breaker"))
+    );
+    let result = provider
+        .inner_request(None, helper_context.clone(), query(), String::new())
+        .await;
+    assert!(
+        matches!(result, Err(DataApiError::Connection(message)) if message.contains("circuit breaker"))
+    );
+
+    provider
+        .on_config_update(&config("http://127.0.0.1:1"))
+        .await
+        .unwrap();
+    let result = provider
+        .inner_request(None, helper_context, query(), String::new())
+        .await;
+    assert!(
+        matches!(result, Err(DataApiError::Connection(message)) if !message.contains("circuit breaker"))
+    );
+}
+
+#[test]
+fn test_request_compression() {
+    let config =
+        |value: &str| HashMap::from([(String::from("data-api-compression"), value.to_string())]);
+
+    assert_eq!(request_compression(&HashMap::new()), None);
+    assert_eq!(request_compression(&config("none")), None);
+    assert_eq!(
+        request_compression(&config("gzip")),
+        Some(CompressionEncoding::Gzip)
+    );
+    assert_eq!(
+        request_compression(&config("zstd")),
+        Some(CompressionEncoding::Zstd)
+    );
+    assert_eq!(request_compression(&config("brotli")), None);
+}
+
+#[tokio::test]
+async fn test_compression_and_message_limits() {
+    let address = start_mock_data_api(mock_data_api::MockDataApi::default()).await;
+    let helper_context = test_context("application");
+    let create =
+        || String::from("mutation($input: UserInput) { createUser(input: $input) { id } }");
+    // the limits apply to compressed messages, so the name has to be hard to compress
+    let name = (0..250).map(|_| random_string()).collect::<String>();
+    let input = || format!(r#"{{"input": {{"name": "{name}"}}}}"#);
+
+    let provider = DataAPIProvider::new(test_config(
+        &address,
+        [
+            ("retry-max-attempts", "1"),
+            ("data-api-compression", "zstd"),
+            ("data-api-max-decoding-message-size", "1024"),
+        ],
+    ));
+    let created = provider
+        .inner_request(None, helper_context.clone(), create(), input())
+        .await
+        .unwrap();
+    assert_eq!(created, r#"{"createUser":{"id":"1"}}"#);
+
+    // the response of the record is larger than the limit
+    let result = provider
+        .inner_request(
+            None,
+            helper_context.clone(),
+            String::from("{allUser{results{id name}}}"),
+            String::new(),
+        )
+        .await;
+    assert!(result.is_err());
+
+    let provider = DataAPIProvider::new(test_config(
+        &address,
+        [
+            ("retry-max-attempts", "1"),
+            ("data-api-compression", "gzip"),
+            ("data-api-max-encoding-message-size", "1024"),
+        ],
+    ));
+    let result = provider
+        .inner_request(None, helper_context, create(), input())
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_health_request() {
+    let address = start_mock_data_api(mock_data_api::MockDataApi::default()).await;
+    let config = |address: &str| test_config(address, [("health-check-timeout-ms", "1000")]);
+    let request = HealthCheckRequest {};
+
+    let provider = DataAPIProvider::new(config(&address));
+    let response = provider.health_request(&request).await.unwrap();
+    assert!(response.healthy, "{:?}", response.message);
+    assert_eq!(response.message, None);
+
+    let mut missing_secret = config("http://127.0.0.1:1");
+    missing_secret.insert(
+        String::from("jaws-secret-file"),
+        String::from("/nonexistent/jaws-secret"),
+    );
+    let provider = DataAPIProvider::new(missing_secret);
+    let response = provider.health_request(&request).await.unwrap();
+    assert!(!response.healthy);
+    let message = response.message.unwrap();
+    assert!(message.contains("data-api http://127.0.0.1:1 is not healthy"));
+    assert!(message.contains("jaws secret is not retrievable"));
+
+    // dedicated clusters that do not respond only degrade the provider, and are checked
+    // concurrently within the timeout
+    let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let silent_address = format!("http://{}", silent.local_addr().unwrap());
+    let mut config = config(&address);
+    config.insert(
+        String::from("data-api-routes"),
+        format!(r#"{{"dedicated-1": "{silent_address}", "dedicated-2": "http://127.0.0.1:1"}}"#),
+    );
+    let provider = DataAPIProvider::new(config);
+
+    let started = Instant::now();
+    let response = provider.health_request(&request).await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(1500));
+    assert!(response.healthy);
+    let message = response.message.unwrap();
+    assert!(message.contains(&format!("data-api {silent_address} is not healthy")));
+    assert!(message.contains("data-api http://127.0.0.1:1 is not healthy"));
 }
@@ -0,0 +1,174 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use moka::future::Cache;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::provider::bindings::exports::betty_blocks::data_api::data_api::DataApiError;
+
+// Limits of applications that stopped sending requests are forgotten after this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Token bucket and in-flight limits per application, so a single runaway action can not flood
+/// the data-api.
+///
+/// A `requests_per_second` or `max_in_flight` of 0 disables that limit.
+#[derive(Clone)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    max_in_flight: usize,
+    applications: Cache<String, Arc<Limits>>,
+}
+
+struct Limits {
+    bucket: Mutex<Bucket>,
+    in_flight: Arc<Semaphore>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Holds a slot of the in-flight limit of an application until it is dropped.
+pub struct Permit {
+    _in_flight: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// `burst` is the number of requests that can be sent at once after being idle, it defaults
+    /// to one second worth of requests when 0.
+    pub fn new(requests_per_second: f64, burst: u32, max_in_flight: usize) -> Self {
+        let burst = match burst {
+            0 => requests_per_second.ceil().max(1.0),
+            burst => f64::from(burst),
+        };
+
+        Self {
+            requests_per_second,
+            burst,
+            max_in_flight,
+            applications: Cache::builder().time_to_idle(IDLE_TIMEOUT).build(),
+        }
+    }
+
+    /// Takes `cost` tokens from the bucket of the application and a slot of its in-flight limit.
+    /// A `cost` above the burst is never allowed, so batches can not send more than the burst.
+    pub async fn acquire(&self, application_id: &str, cost: u32) -> Result<Permit, DataApiError> {
+        if self.requests_per_second <= 0.0 && self.max_in_flight == 0 {
+            return Ok(Permit { _in_flight: None });
+        }
+
+        let limits = self
+            .applications
+            .get_with(application_id.to_string(), async {
+                Arc::new(Limits {
+                    bucket: Mutex::new(Bucket {
+                        tokens: self.burst,
+                        updated: Instant::now(),
+                    }),
+                    in_flight: Arc::new(Semaphore::new(self.max_in_flight)),
+                })
+            })
+            .await;
+
+        let in_flight = match self.max_in_flight {
+            0 => None,
+            max_in_flight => Some(limits.in_flight.clone().try_acquire_owned().map_err(|_| {
+                DataApiError::RateLimited(format!(
+                    "application {application_id} has {max_in_flight} requests in flight already"
+                ))
+            })?),
+        };
+
+        if self.requests_per_second > 0.0 {
+            if f64::from(cost) > self.burst {
+                return Err(DataApiError::RateLimited(format!(
+                    "{cost} requests at once exceed the burst of {} requests",
+                    self.burst
+                )));
+            }
+
+            self.take_tokens(&limits.bucket, f64::from(cost))
+                .map_err(|_| {
+                    DataApiError::RateLimited(format!(
+                        "application {application_id} exceeded {} requests per second",
+                        self.requests_per_second
+                    ))
+                })?;
+        }
+
+        Ok(Permit {
+            _in_flight: in_flight,
+        })
+    }
+
+    fn take_tokens(&self, bucket: &Mutex<Bucket>, cost: f64) -> Result<(), ()> {
+        let mut bucket = bucket.lock().expect("rate limit lock is not poisoned");
+
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.requests_per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens < cost {
+            return Err(());
+        }
+
+        bucket.tokens -= cost;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limiter_token_bucket() {
+    let limiter = RateLimiter::new(100.0, 2, 0);
+
+    assert!(limiter.acquire("app", 1).await.is_ok());
+    assert!(limiter.acquire("app", 1).await.is_ok());
+    assert!(matches!(
+        limiter.acquire("app", 1).await,
+        Err(DataApiError::RateLimited(_))
+    ));
+
+    // other applications have their own bucket
+    assert!(limiter.acquire("other-app", 1).await.is_ok());
+
+    // 100 requests per second refill a token every 10ms
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(limiter.acquire("app", 1).await.is_ok());
+
+    // a cost above the burst is rejected instead of taking only the whole bucket
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert!(matches!(
+        limiter.acquire("app", 10).await,
+        Err(DataApiError::RateLimited(_))
+    ));
+    assert!(limiter.acquire("app", 2).await.is_ok());
+    assert!(limiter.acquire("app", 1).await.is_err());
+}
+
+#[tokio::test]
+async fn test_rate_limiter_in_flight() {
+    let limiter = RateLimiter::new(0.0, 0, 2);
+
+    let first = limiter.acquire("app", 1).await.unwrap();
+    let _second = limiter.acquire("app", 1).await.unwrap();
+    assert!(matches!(
+        limiter.acquire("app", 1).await,
+        Err(DataApiError::RateLimited(_))
+    ));
+
+    drop(first);
+    assert!(limiter.acquire("app", 1).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_rate_limiter_disabled() {
+    let limiter = RateLimiter::new(0.0, 0, 0);
+
+    for _ in 0..100 {
+        assert!(limiter.acquire("app", 1).await.is_ok());
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::provider::bindings::exports::betty_blocks::data_api::data_api::DataApiError;
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with full jitter, `attempt` starts at 1 for the first retry.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let cap = exponential.min(self.max_delay);
+
+        rand::rng().random_range(Duration::ZERO..=cap)
+    }
+}
+
+/// Errors that are caused by the data-api being (temporarily) unreachable.
+pub fn is_transient(error: &DataApiError) -> bool {
+    matches!(
+        error,
+        DataApiError::Connection(_) | DataApiError::Timeout(_)
+    )
+}
+
+/// GraphQL queries can safely be sent again, mutations can not.
+pub fn is_idempotent(query: &str) -> bool {
+    !query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == "mutation")
+}
+
+#[test]
+fn test_backoff_is_capped() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+    };
+
+    for attempt in 1..=10 {
+        assert!(policy.backoff(attempt) <= Duration::from_millis(500));
+    }
+    assert!(policy.backoff(1) <= Duration::from_millis(100));
+    assert!(policy.backoff(u32::MAX) <= Duration::from_millis(500));
+}
+
+#[test]
+fn test_is_idempotent() {
+    assert!(is_idempotent("{allUser{results{id}}}"));
+    assert!(is_idempotent(
+        "fragment userFields on User { id } query($where: UserFilterInput) { oneUser(where: $where) { ...userFields } }"
+    ));
+    assert!(!is_idempotent(
+        "mutation($input: UserInput) { createUser(input: $input) { id } }"
+    ));
+    assert!(!is_idempotent("mutation{deleteUser(id: 1){id}}"));
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Picks the data-api endpoint of an application, so applications can live on dedicated
+/// data-api clusters.
+///
+/// Routes are keyed by application id, a key ending in `*` matches every application id that
+/// starts with the rest of the key. Exact routes win over prefixes and longer prefixes win over
+/// shorter ones. Applications without a route use the default address.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutingTable {
+    default: String,
+    exact: HashMap<String, String>,
+    // longest prefix first
+    prefixes: Vec<(String, String)>,
+    // per address, for clusters whose certificate is not issued for the host of the address
+    tls_domains: HashMap<String, String>,
+}
+
+/// The address of a route, optionally with the domain its TLS certificate is issued for.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Route {
+    Address(String),
+    Tls {
+        address: String,
+        #[serde(rename = "tls-domain")]
+        tls_domain: String,
+    },
+}
+
+impl RoutingTable {
+    pub fn new(default: String, routes: HashMap<String, String>) -> Self {
+        let mut exact = HashMap::new();
+        let mut prefixes = Vec::new();
+
+        for (key, address) in routes {
+            match key.strip_suffix('*') {
+                Some(prefix) => prefixes.push((prefix.to_string(), address)),
+                None => {
+                    exact.insert(key, address);
+                }
+            }
+        }
+        prefixes.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+        Self {
+            default,
+            exact,
+            prefixes,
+            tls_domains: HashMap::new(),
+        }
+    }
+
+    /// Parses the routes from a JSON object of application ids (or prefixes) to addresses, or
+    /// to objects with an `address` and a `tls-domain`.
+    pub fn parse(default: String, routes: &str) -> anyhow::Result<Self> {
+        let routes: HashMap<String, Route> = serde_json::from_str(routes).map_err(|e| {
+            anyhow::anyhow!("data-api routes are not a json object of addresses: {e}")
+        })?;
+
+        let mut addresses = HashMap::new();
+        let mut tls_domains = HashMap::new();
+        for (key, route) in routes {
+            let address = match route {
+                Route::Address(address) => address,
+                Route::Tls {
+                    address,
+                    tls_domain,
+                } => {
+                    tls_domains.insert(address.clone(), tls_domain);
+                    address
+                }
+            };
+            addresses.insert(key, address);
+        }
+
+        Ok(Self {
+            tls_domains,
+            ..Self::new(default, addresses)
+        })
+    }
+
+    /// The domain the certificate of the data-api at `address` is verified against, when it is
+    /// not the host of the address.
+    pub fn tls_domain(&self, address: &str) -> Option<&str> {
+        self.tls_domains.get(address).map(String::as_str)
+    }
+
+    /// Every address applications can be routed to, without duplicates, the default first.
+    pub fn addresses(&self) -> Vec<&str> {
+        let mut addresses = vec![self.default.as_str()];
+        for address in self
+            .exact
+            .values()
+            .chain(self.prefixes.iter().map(|(_, a)| a))
+        {
+            if !addresses.contains(&address.as_str()) {
+                addresses.push(address);
+            }
+        }
+        addresses
+    }
+
+    pub fn address(&self, application_id: &str) -> &str {
+        if let Some(address) = self.exact.get(application_id) {
+            return address;
+        }
+
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| application_id.starts_with(prefix.as_str()))
+            .map_or(&self.default, |(_, address)| address)
+    }
+}
+
+#[test]
+fn test_routing_table() {
+    let table = RoutingTable::parse(
+        String::from("http://data-api:50054"),
+        r#"{
+            "app-1": "http://app-1:50054",
+            "enterprise-*": "http://enterprise:50054",
+            "enterprise-eu-*": "http://enterprise-eu:50054",
+            "enterprise-eu-2": "http://enterprise-eu-2:50054"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(table.address("app-1"), "http://app-1:50054");
+    assert_eq!(table.address("app-10"), "http://data-api:50054");
+    assert_eq!(table.address("enterprise-us-1"), "http://enterprise:50054");
+    assert_eq!(
+        table.address("enterprise-eu-1"),
+        "http://enterprise-eu:50054"
+    );
+    assert_eq!(
+        table.address("enterprise-eu-2"),
+        "http://enterprise-eu-2:50054"
+    );
+
+    let mut addresses = table.addresses();
+    addresses[1..].sort();
+    assert_eq!(
+        addresses,
+        vec![
+            "http://data-api:50054",
+            "http://app-1:50054",
+            "http://enterprise-eu-2:50054",
+            "http://enterprise-eu:50054",
+            "http://enterprise:50054",
+        ]
+    );
+
+    let table = RoutingTable::new(String::from("http://data-api:50054"), HashMap::new());
+    assert_eq!(table.addresses(), vec!["http://data-api:50054"]);
+    assert_eq!(table.address("app-1"), "http://data-api:50054");
+
+    assert!(RoutingTable::parse(String::new(), r#"["app-1"]"#).is_err());
+}
+
+#[test]
+fn test_routing_table_tls_domains() {
+    let table = RoutingTable::parse(
+        String::from("https://data-api:50054"),
+        r#"{
+            "app-1": {"address": "https://10.0.0.1:50054", "tls-domain": "app-1.data-api"},
+            "app-2": "https://app-2:50054"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(table.address("app-1"), "https://10.0.0.1:50054");
+    assert_eq!(
+        table.tls_domain("https://10.0.0.1:50054"),
+        Some("app-1.data-api")
+    );
+    assert_eq!(table.tls_domain("https://app-2:50054"), None);
+    assert_eq!(table.tls_domain("https://data-api:50054"), None);
+
+    assert!(RoutingTable::parse(String::new(), r#"{"app-1": {"address": 1}}"#).is_err());
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use jaws_rs::jsonwebtoken::{EncodingKey, Header};
+
+const DEFAULT_JAWS_SECRET_NAME: &str = "ACTIONS_WASM_DATA_API_SECRET";
+
+/// Which key of the key set signs a jaws token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyRole {
+    Active,
+    /// Only used while rotating, when the data-api does not accept the active key yet.
+    Previous,
+}
+
+/// Where the HMAC secret of a signing key is read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SecretSource {
+    KeyVault(String),
+    Env(String),
+    File(PathBuf),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningKey {
+    pub kid: Option<String>,
+    pub source: SecretSource,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeySet {
+    pub active: SigningKey,
+    pub previous: Option<SigningKey>,
+}
+
+impl KeySet {
+    /// Reads the key set from the `jaws-*` keys of the provider config.
+    ///
+    /// `jaws-key-source` selects where the secrets come from: `key-vault` (default), `env` or
+    /// `file`. The active key is named by `jaws-secret-key`, `jaws-secret-env` or
+    /// `jaws-secret-file`, the previous key by the same keys with a `jaws-previous-` prefix.
+    pub fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let source = config
+            .get("jaws-key-source")
+            .map(String::as_str)
+            .unwrap_or("key-vault");
+
+        let active = signing_key(config, source, "jaws")?.ok_or_else(|| {
+            anyhow::anyhow!("jaws-secret-file must be configured for the file key source")
+        })?;
+        let previous = signing_key(config, source, "jaws-previous")?;
+
+        Ok(Self { active, previous })
+    }
+
+    pub fn get(&self, role: KeyRole) -> Option<&SigningKey> {
+        match role {
+            KeyRole::Active => Some(&self.active),
+            KeyRole::Previous => self.previous.as_ref(),
+        }
+    }
+}
+
+fn signing_key(
+    config: &HashMap<String, String>,
+    source: &str,
+    prefix: &str,
+) -> anyhow::Result<Option<SigningKey>> {
+    let name = |kind: &str| config.get(&format!("{prefix}-secret-{kind}")).cloned();
+    // NOTE:
+    // The active key falls back to the secret name that was used before key sets existed
+    let default_name = (prefix == "jaws").then(|| String::from(DEFAULT_JAWS_SECRET_NAME));
+
+    let source = match source {
+        "key-vault" => name("key").or(default_name).map(SecretSource::KeyVault),
+        "env" => name("env").or(default_name).map(SecretSource::Env),
+        "file" => name("file").map(|path| SecretSource::File(path.into())),
+        other => anyhow::bail!("unknown jaws-key-source: {other}"),
+    };
+
+    Ok(source.map(|source| SigningKey {
+        kid: config.get(&format!("{prefix}-key-id")).cloned(),
+        source,
+    }))
+}
+
+/// Signs the claims like `jaws_rs::encode`, with the `kid` of the signing key in the header.
+pub fn encode(
+    claims: &jaws_rs::Claims,
+    kid: Option<String>,
+    secret: &str,
+) -> anyhow::Result<String> {
+    let Some(kid) = kid else {
+        return Ok(jaws_rs::encode(claims, secret)?);
+    };
+
+    let header = Header {
+        kid: Some(kid),
+        ..Header::default()
+    };
+
+    Ok(jaws_rs::jsonwebtoken::encode(
+        &header,
+        claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )?)
+}
+
+#[test]
+fn test_key_set_from_config() {
+    let key_set = KeySet::from_config(&HashMap::new()).unwrap();
+    assert_eq!(
+        key_set.active,
+        SigningKey {
+            kid: None,
+            source: SecretSource::KeyVault(String::from("ACTIONS_WASM_DATA_API_SECRET")),
+        }
+    );
+    assert_eq!(key_set.previous, None);
+    assert_eq!(key_set.get(KeyRole::Previous), None);
+
+    let config = HashMap::from([
+        (String::from("jaws-key-source"), String::from("env")),
+        (String::from("jaws-secret-env"), String::from("JAWS_SECRET")),
+        (String::from("jaws-key-id"), String::from("2025-02")),
+        (
+            String::from("jaws-previous-secret-env"),
+            String::from("JAWS_PREVIOUS_SECRET"),
+        ),
+        (
+            String::from("jaws-previous-key-id"),
+            String::from("2025-01"),
+        ),
+    ]);
+    let key_set = KeySet::from_config(&config).unwrap();
+    assert_eq!(
+        key_set.get(KeyRole::Active),
+        Some(&SigningKey {
+            kid: Some(String::from("2025-02")),
+            source: SecretSource::Env(String::from("JAWS_SECRET")),
+        })
+    );
+    assert_eq!(
+        key_set.get(KeyRole::Previous),
+        Some(&SigningKey {
+            kid: Some(String::from("2025-01")),
+            source: SecretSource::Env(String::from("JAWS_PREVIOUS_SECRET")),
+        })
+    );
+
+    let config = HashMap::from([(String::from("jaws-key-source"), String::from("file"))]);
+    assert!(KeySet::from_config(&config).is_err());
+
+    let config = HashMap::from([(String::from("jaws-key-source"), String::from("vault"))]);
+    assert!(KeySet::from_config(&config).is_err());
+}
+
+#[test]
+fn test_encode_sets_kid() {
+    let claims = jaws_rs::Claims::new(
+        String::from("actions-wasm"),
+        String::from("application"),
+        0,
+        String::from("jti"),
+    );
+
+    let token = encode(&claims, Some(String::from("2025-02")), "secret").unwrap();
+    let header = jaws_rs::jsonwebtoken::decode_header(&token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("2025-02"));
+
+    let token = encode(&claims, None, "secret").unwrap();
+    let header = jaws_rs::jsonwebtoken::decode_header(&token).unwrap();
+    assert_eq!(header.kid, None);
+}
@@ -0,0 +1,193 @@
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+/// PEM encoded certificates used to connect to the data-api over TLS.
+///
+/// Without a `ca` the server certificate is verified against the webpki roots. Setting both
+/// `cert` and `key` presents a client certificate to the data-api (mTLS).
+#[derive(Clone, Debug, Default)]
+pub struct TlsSettings {
+    pub ca: Option<String>,
+    pub cert: Option<String>,
+    pub key: Option<String>,
+    pub domain: Option<String>,
+}
+
+impl TlsSettings {
+    pub fn client_tls_config(&self) -> anyhow::Result<ClientTlsConfig> {
+        let mut tls = ClientTlsConfig::new();
+
+        tls = match &self.ca {
+            Some(ca) => tls.ca_certificate(Certificate::from_pem(ca)),
+            None => tls.with_webpki_roots(),
+        };
+
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => tls = tls.identity(Identity::from_pem(cert, key)),
+            (None, None) => {}
+            _ => anyhow::bail!("a client certificate and key must be configured together"),
+        }
+
+        if let Some(domain) = &self.domain {
+            tls = tls.domain_name(domain);
+        }
+
+        Ok(tls)
+    }
+}
+
+#[cfg(test)]
+struct Pki {
+    ca: String,
+    server: rcgen::CertifiedKey,
+    client: rcgen::CertifiedKey,
+}
+
+#[cfg(test)]
+fn pki() -> Pki {
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedKey, IsCa, KeyPair};
+
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let issue = |name: &str| {
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec![String::from(name)])
+            .unwrap()
+            .signed_by(&key_pair, &ca, &ca_key)
+            .unwrap();
+        CertifiedKey { cert, key_pair }
+    };
+
+    Pki {
+        ca: ca.pem(),
+        server: issue("localhost"),
+        client: issue("data-api-provider"),
+    }
+}
+
+#[cfg(test)]
+async fn serve(tls: tonic::transport::ServerTlsConfig) -> String {
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(
+        Server::builder()
+            .tls_config(tls)
+            .unwrap()
+            .add_service(mock_data_api::MockDataApi::default().into_server())
+            .serve_with_incoming(TcpIncoming::from(listener)),
+    );
+
+    format!("https://localhost:{port}")
+}
+
+#[cfg(test)]
+async fn execute(address: &str, settings: &TlsSettings) -> Result<String, tonic::Status> {
+    use crate::channel_pool::ChannelPool;
+    use crate::provider::data_grpc::data_api_client::DataApiClient;
+    use crate::provider::data_grpc::DataApiRequest;
+
+    let pool = ChannelPool::new(
+        address,
+        1,
+        std::time::Duration::from_secs(30),
+        Some(settings.client_tls_config().unwrap()),
+    )
+    .unwrap();
+
+    let mut request = tonic::Request::new(DataApiRequest {
+        query: String::from("{allUser{results{id}}}"),
+        variables: String::from("{}"),
+        context: None,
+    });
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer jaws".parse().unwrap());
+
+    let response = DataApiClient::new(pool.channel()).execute(request).await?;
+
+    Ok(response.into_inner().result)
+}
+
+#[test]
+fn test_client_tls_config_requires_cert_and_key() {
+    let settings = TlsSettings {
+        cert: Some(String::from("cert")),
+        ..Default::default()
+    };
+    assert!(settings.client_tls_config().is_err());
+
+    let settings = TlsSettings {
+        key: Some(String::from("key")),
+        ..Default::default()
+    };
+    assert!(settings.client_tls_config().is_err());
+
+    assert!(TlsSettings::default().client_tls_config().is_ok());
+}
+
+#[tokio::test]
+async fn test_tls_with_custom_ca() {
+    let pki = pki();
+    let address = serve(
+        tonic::transport::ServerTlsConfig::new().identity(Identity::from_pem(
+            pki.server.cert.pem(),
+            pki.server.key_pair.serialize_pem(),
+        )),
+    )
+    .await;
+
+    let settings = TlsSettings {
+        ca: Some(pki.ca.clone()),
+        ..Default::default()
+    };
+    assert!(execute(&address, &settings)
+        .await
+        .unwrap()
+        .starts_with(r#"{"allUser""#));
+
+    // the self-signed certificate is not trusted by the default roots
+    assert!(execute(&address, &TlsSettings::default()).await.is_err());
+
+    // the certificate is not issued for another domain
+    let settings = TlsSettings {
+        ca: Some(pki.ca),
+        domain: Some(String::from("data-api.example.com")),
+        ..Default::default()
+    };
+    assert!(execute(&address, &settings).await.is_err());
+}
+
+#[tokio::test]
+async fn test_mtls() {
+    let pki = pki();
+    let address = serve(
+        tonic::transport::ServerTlsConfig::new()
+            .identity(Identity::from_pem(
+                pki.server.cert.pem(),
+                pki.server.key_pair.serialize_pem(),
+            ))
+            .client_ca_root(Certificate::from_pem(&pki.ca)),
+    )
+    .await;
+
+    let settings = TlsSettings {
+        ca: Some(pki.ca.clone()),
+        cert: Some(pki.client.cert.pem()),
+        key: Some(pki.client.key_pair.serialize_pem()),
+        domain: None,
+    };
+    assert!(execute(&address, &settings).await.is_ok());
+
+    // the server rejects clients without a certificate
+    let settings = TlsSettings {
+        ca: Some(pki.ca),
+        ..Default::default()
+    };
+    assert!(execute(&address, &settings).await.is_err());
+}
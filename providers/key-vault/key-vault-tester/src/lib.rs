@@ -10,7 +10,7 @@ mod bindings {
             "wasi:io/error@0.2.2": wasmcloud_component::wasi::io::error,
             "wasi:io/poll@0.2.2": wasmcloud_component::wasi::io::poll,
             "wasi:io/streams@0.2.2": wasmcloud_component::wasi::io::streams,
-            "betty-blocks:key-vault/key-vault": generate
+            "betty-blocks:key-vault/key-vault@0.2.0": generate
         }
     });
 
@@ -29,7 +29,10 @@ impl http::Server for KeyFetcher {
         request: http::IncomingRequest,
     ) -> http::Result<http::Response<impl http::OutgoingBody>, http::ErrorCode> {
         let key = request.uri().path().replace("/", "");
-        let outkey = key_vault::get_secret(&key).unwrap_or(String::from(EMPTY_MESSAGE));
+        let outkey = match key_vault::get_secret(&key) {
+            Ok(secret) => secret.unwrap_or(String::from(EMPTY_MESSAGE)),
+            Err(e) => format!("{e:?}"),
+        };
         Ok(http::Response::new(outkey))
     }
 }
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+
+mod azure;
+mod directory;
+mod env;
+mod mock;
+mod vault;
+
+pub use azure::AzureBackend;
+pub use directory::DirectoryBackend;
+pub use env::EnvBackend;
+pub use mock::MockBackend;
+pub use vault::VaultBackend;
+
+/// The keys of a secret, values can be any JSON value.
+pub type Values = serde_json::Map<String, serde_json::Value>;
+
+/// The secret changed since the version that was written back was read.
+#[derive(Debug)]
+pub struct Conflict;
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the secret changed since it was read")
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// The backend can only be read from, or can not write without losing concurrent writes.
+#[derive(Debug)]
+pub struct ReadOnly;
+
+impl std::fmt::Display for ReadOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the backend does not support writing secrets")
+    }
+}
+
+impl std::error::Error for ReadOnly {}
+
+/// Where the provider reads secrets from. A secret is a named set of keys and values, which is
+/// read, cached and written as a whole.
+#[async_trait::async_trait]
+pub trait SecretBackend: Debug + Send + Sync {
+    /// The keys and values of secret `name`, empty when it does not exist.
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values>;
+
+    /// Like `fetch`, with the version the secret is at, `None` when it does not exist.
+    async fn fetch_versioned(&self, name: &str) -> anyhow::Result<(Values, Option<String>)> {
+        Ok((self.fetch(name).await?, None))
+    }
+
+    /// Replaces the values of secret `name`, failing with [`Conflict`] when it is no longer at
+    /// `version`.
+    async fn store(
+        &self,
+        _name: &str,
+        _values: &Values,
+        _version: Option<&str>,
+    ) -> anyhow::Result<()> {
+        Err(ReadOnly.into())
+    }
+}
+
+/// Picks the backend from the `backend` config, `azure` when it is not set. A `keyvault_mock`
+/// is used instead of any backend, for running the provider locally.
+pub fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Arc<dyn SecretBackend>> {
+    if let Some(keyvault_mock) = config.get("keyvault_mock") {
+        return Ok(Arc::new(MockBackend::new(keyvault_mock.clone())));
+    }
+
+    let backend = config.get("backend").map_or("azure", String::as_str);
+    let required = |key: &str| {
+        config
+            .get(key)
+            .cloned()
+            .with_context(|| format!("{key} is required for the {backend} backend"))
+    };
+
+    Ok(match backend {
+        "azure" => Arc::new(AzureBackend::new(required("endpoint")?)),
+        "vault" => Arc::new(VaultBackend::new(
+            required("vault_address")?,
+            vault::token(config)?,
+            config.get("vault_mount").cloned(),
+        )),
+        "directory" => Arc::new(DirectoryBackend::new(required("secrets_path")?.into())),
+        "env" => Arc::new(EnvBackend),
+        backend => anyhow::bail!("unknown key-vault backend: {backend}"),
+    })
+}
+
+/// Backends that store a secret as a single value hold a JSON object.
+pub fn parse_json(json: &str) -> anyhow::Result<Values> {
+    serde_json::from_str(json).context("secret is not a json object")
+}
+
+/// The value of `key`, a key containing dots also looks up nested objects: `smtp.host` is read
+/// from `{"smtp": {"host": ..}}` when there is no `smtp.host` key. Strings are returned as is,
+/// other values as JSON.
+pub fn lookup(values: &Values, key: &str) -> Option<String> {
+    let value = values.get(key).or_else(|| {
+        let mut path = key.split('.');
+        let first = values.get(path.next()?)?;
+        path.try_fold(first, |value, segment| match value {
+            serde_json::Value::Object(object) => object.get(segment),
+            serde_json::Value::Array(array) => array.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+    })?;
+
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Removes `key` the way [`lookup`] finds it, returning whether anything was removed.
+pub fn remove(values: &mut Values, key: &str) -> bool {
+    if values.remove(key).is_some() {
+        return true;
+    }
+
+    let Some((parent, last)) = key.rsplit_once('.') else {
+        return false;
+    };
+    let mut path = parent.split('.');
+    let first = path.next().and_then(|first| values.get_mut(first));
+    let parent = path.try_fold(first, |value, segment| {
+        Some(match value? {
+            serde_json::Value::Object(object) => object.get_mut(segment),
+            serde_json::Value::Array(array) => array.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+    });
+
+    match parent.flatten() {
+        Some(serde_json::Value::Object(object)) => object.remove(last).is_some(),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_from_config() {
+    let config = |entries: &[(&str, &str)]| {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let backend = from_config(&config(&[("endpoint", "https://example.vault.azure.net/")]));
+    assert!(format!("{:?}", backend.unwrap()).starts_with("AzureBackend"));
+
+    let backend = from_config(&config(&[("backend", "env"), ("keyvault_mock", "{}")]));
+    assert!(format!("{:?}", backend.unwrap()).starts_with("MockBackend"));
+
+    let backend = from_config(&config(&[("backend", "directory"), ("secrets_path", "/")]));
+    assert!(format!("{:?}", backend.unwrap()).starts_with("DirectoryBackend"));
+
+    let backend = from_config(&config(&[
+        ("backend", "vault"),
+        ("vault_address", "http://127.0.0.1:8200"),
+        ("vault_token", "token"),
+    ]));
+    assert!(format!("{:?}", backend.unwrap()).starts_with("VaultBackend"));
+
+    assert!(from_config(&config(&[("backend", "azure")])).is_err());
+    assert!(from_config(&config(&[("backend", "directory")])).is_err());
+    assert!(from_config(&config(&[("backend", "kubernetes")])).is_err());
+}
+
+#[test]
+fn test_lookup() {
+    let values = parse_json(
+        r#"{
+            "secret": "test",
+            "port": 587,
+            "tls": true,
+            "empty": null,
+            "smtp": {"host": "smtp.example.com", "auth": {"username": "betty"}},
+            "smtp.host": "flat.example.com",
+            "hosts": ["a.example.com", "b.example.com"]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(lookup(&values, "secret").as_deref(), Some("test"));
+    assert_eq!(lookup(&values, "port").as_deref(), Some("587"));
+    assert_eq!(lookup(&values, "tls").as_deref(), Some("true"));
+    assert_eq!(lookup(&values, "empty"), None);
+    assert_eq!(lookup(&values, "missing"), None);
+    assert_eq!(
+        lookup(&values, "smtp.auth.username").as_deref(),
+        Some("betty")
+    );
+    assert_eq!(
+        lookup(&values, "smtp.auth").as_deref(),
+        Some(r#"{"username":"betty"}"#)
+    );
+    // keys containing dots win over nested objects
+    assert_eq!(
+        lookup(&values, "smtp.host").as_deref(),
+        Some("flat.example.com")
+    );
+    assert_eq!(lookup(&values, "hosts.1").as_deref(), Some("b.example.com"));
+    assert_eq!(lookup(&values, "secret.length"), None);
+
+    assert!(parse_json(r#"["secret"]"#).is_err());
+}
+
+#[test]
+fn test_remove() {
+    let mut values = parse_json(
+        r#"{
+            "secret": "test",
+            "smtp": {"host": "smtp.example.com", "auth": {"username": "betty"}},
+            "hosts": [{"name": "a.example.com"}]
+        }"#,
+    )
+    .unwrap();
+
+    assert!(remove(&mut values, "secret"));
+    assert!(remove(&mut values, "smtp.auth.username"));
+    assert!(remove(&mut values, "hosts.0.name"));
+    assert!(!remove(&mut values, "smtp.port"));
+    assert!(!remove(&mut values, "smtp.host.name"));
+    assert!(!remove(&mut values, "missing.host"));
+
+    assert_eq!(
+        values,
+        parse_json(r#"{"smtp": {"host": "smtp.example.com", "auth": {}}, "hosts": [{}]}"#).unwrap()
+    );
+    assert_eq!(lookup(&values, "smtp.auth.username"), None);
+}
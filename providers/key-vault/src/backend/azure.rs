@@ -0,0 +1,34 @@
+use azure_identity::ManagedIdentityCredential;
+use azure_security_keyvault_secrets::SecretClient;
+
+use super::{parse_json, SecretBackend, Values};
+
+/// Azure Key Vault, each secret holds a JSON object.
+///
+/// Secrets are only read, Key Vault has no conditional writes so writing them could overwrite
+/// values that were written concurrently.
+#[derive(Debug)]
+pub struct AzureBackend {
+    endpoint: String,
+}
+
+impl AzureBackend {
+    pub fn new(endpoint: String) -> Self {
+        Self { endpoint }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretBackend for AzureBackend {
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values> {
+        let credential = ManagedIdentityCredential::new(None)?;
+        let client = SecretClient::new(&self.endpoint, credential.clone(), None)?;
+
+        let secret_response = client.get_secret(name, None).await?;
+        let secret_ref = secret_response.into_body().await?;
+        match secret_ref.value {
+            Some(secret) => parse_json(&secret),
+            None => Ok(Values::new()),
+        }
+    }
+}
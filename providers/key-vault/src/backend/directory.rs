@@ -0,0 +1,95 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+
+use super::{parse_json, SecretBackend, Values};
+
+/// A mounted directory, like a Kubernetes secret volume. Secret `name` is either a directory
+/// with a file per key, or a file holding a JSON object.
+#[derive(Debug)]
+pub struct DirectoryBackend {
+    path: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretBackend for DirectoryBackend {
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values> {
+        // NOTE:
+        // Names are not always validated by the provider, so they are checked here to not read
+        // anything outside of the directory
+        let valid = !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..");
+        anyhow::ensure!(valid, "invalid secret name: {name:?}");
+        let path = self.path.join(name);
+
+        let metadata = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Values::new()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+
+        if !metadata.is_dir() {
+            let json = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            return parse_json(&json);
+        }
+
+        let mut secrets = Values::new();
+        let mut entries = tokio::fs::read_dir(&path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let key = entry.file_name().to_string_lossy().to_string();
+            // NOTE:
+            // Kubernetes keeps the real files in hidden directories and links to them
+            if key.starts_with('.') || !tokio::fs::metadata(entry.path()).await?.is_file() {
+                continue;
+            }
+
+            let value = tokio::fs::read_to_string(entry.path())
+                .await
+                .with_context(|| format!("failed to read {}", entry.path().display()))?;
+            secrets.insert(key, value.trim_end_matches('\n').into());
+        }
+
+        Ok(secrets)
+    }
+}
+
+#[tokio::test]
+async fn test_directory_backend() {
+    let path = std::env::temp_dir().join(format!("key-vault-test-{}", std::process::id()));
+    std::fs::create_dir_all(path.join("data-api-secrets/..data")).unwrap();
+    std::fs::write(path.join("data-api-secrets/secret"), "test\n").unwrap();
+    std::fs::write(path.join("data-api-secrets/..data/secret"), "hidden").unwrap();
+    std::fs::write(path.join("json-secrets"), r#"{"secret": "json"}"#).unwrap();
+
+    let backend = DirectoryBackend::new(path.clone());
+
+    let secrets = backend.fetch("data-api-secrets").await.unwrap();
+    assert_eq!(secrets, parse_json(r#"{"secret": "test"}"#).unwrap());
+
+    let secrets = backend.fetch("json-secrets").await.unwrap();
+    assert_eq!(secrets["secret"], "json");
+
+    assert!(backend.fetch("missing").await.unwrap().is_empty());
+
+    for name in [
+        "",
+        "/etc/passwd",
+        "../secrets",
+        "data-api-secrets/secret",
+        "..\\secrets",
+    ] {
+        assert!(backend.fetch(name).await.is_err());
+    }
+
+    std::fs::remove_dir_all(path).unwrap();
+}
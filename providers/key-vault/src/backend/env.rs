@@ -0,0 +1,35 @@
+use super::{parse_json, SecretBackend, Values};
+
+/// Environment variables, secret `data-api-secrets` is read from `DATA_API_SECRETS` which holds
+/// a JSON object.
+#[derive(Debug)]
+pub struct EnvBackend;
+
+fn variable_name(name: &str) -> String {
+    name.to_uppercase().replace(['-', '.', '/'], "_")
+}
+
+#[async_trait::async_trait]
+impl SecretBackend for EnvBackend {
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values> {
+        match std::env::var(variable_name(name)) {
+            Ok(json) => parse_json(&json),
+            Err(std::env::VarError::NotPresent) => Ok(Values::new()),
+            Err(e) => Err(anyhow::anyhow!("failed to read secret {name}: {e}")),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_env_backend() {
+    std::env::set_var("KEY_VAULT_TEST_ENV_SECRETS", r#"{"secret": "test"}"#);
+
+    let secrets = EnvBackend
+        .fetch("key-vault-test-env-secrets")
+        .await
+        .unwrap();
+    assert_eq!(secrets["secret"], "test");
+
+    let secrets = EnvBackend.fetch("key-vault-test-missing").await.unwrap();
+    assert!(secrets.is_empty());
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, RwLock};
+
+use super::{parse_json, Conflict, SecretBackend, Values};
+
+/// Serves the same JSON object for every secret name.
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    state: Arc<RwLock<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    json: String,
+    // bumped on every write
+    version: u64,
+}
+
+impl MockBackend {
+    pub fn new(json: String) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(State { json, version: 1 })),
+        }
+    }
+
+    /// Replaces the JSON object, like a secret being rotated.
+    #[cfg(test)]
+    pub fn set(&self, json: &str) {
+        let mut state = self.state.write().expect("mock lock is not poisoned");
+        state.json = json.to_string();
+        state.version += 1;
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretBackend for MockBackend {
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values> {
+        Ok(self.fetch_versioned(name).await?.0)
+    }
+
+    async fn fetch_versioned(&self, _name: &str) -> anyhow::Result<(Values, Option<String>)> {
+        let state = self.state.read().expect("mock lock is not poisoned");
+        Ok((parse_json(&state.json)?, Some(state.version.to_string())))
+    }
+
+    async fn store(
+        &self,
+        _name: &str,
+        values: &Values,
+        version: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut state = self.state.write().expect("mock lock is not poisoned");
+        if version != Some(state.version.to_string().as_str()) {
+            return Err(Conflict.into());
+        }
+
+        state.json = serde_json::to_string(values)?;
+        state.version += 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_mock_backend_store() {
+    let backend = MockBackend::new(String::from(r#"{"secret": "old"}"#));
+
+    let (mut values, version) = backend.fetch_versioned("secrets").await.unwrap();
+    values.insert(String::from("secret"), "new".into());
+    backend
+        .store("secrets", &values, version.as_deref())
+        .await
+        .unwrap();
+    assert_eq!(backend.fetch("secrets").await.unwrap(), values);
+
+    // the version that was read is outdated now
+    let error = backend
+        .store("secrets", &values, version.as_deref())
+        .await
+        .unwrap_err();
+    assert!(error.is::<Conflict>());
+}
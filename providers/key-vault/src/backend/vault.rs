@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use super::{Conflict, SecretBackend, Values};
+
+const DEFAULT_MOUNT: &str = "secret";
+
+/// The KV version 2 secrets engine of HashiCorp Vault.
+pub struct VaultBackend {
+    address: String,
+    token: String,
+    mount: String,
+    client: reqwest::Client,
+}
+
+// The token is left out.
+impl std::fmt::Debug for VaultBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultBackend")
+            .field("address", &self.address)
+            .field("mount", &self.mount)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct ReadResponse {
+    data: ReadData,
+}
+
+#[derive(Deserialize)]
+struct ReadData {
+    data: Values,
+    metadata: Metadata,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    version: u64,
+}
+
+/// The `vault_token` config, or the `VAULT_TOKEN` environment variable, so the token does not
+/// have to be stored in the config.
+pub fn token(config: &HashMap<String, String>) -> anyhow::Result<String> {
+    match config.get("vault_token") {
+        Some(token) => Ok(token.clone()),
+        None => std::env::var("VAULT_TOKEN")
+            .context("vault_token or $VAULT_TOKEN is required for the vault backend"),
+    }
+}
+
+impl VaultBackend {
+    pub fn new(address: String, token: String, mount: Option<String>) -> Self {
+        Self {
+            address: address.trim_end_matches('/').to_string(),
+            token,
+            mount: mount.unwrap_or(DEFAULT_MOUNT.to_string()),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self, name: &str) -> String {
+        format!("{}/v1/{}/data/{name}", self.address, self.mount)
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretBackend for VaultBackend {
+    async fn fetch(&self, name: &str) -> anyhow::Result<Values> {
+        Ok(self.fetch_versioned(name).await?.0)
+    }
+
+    async fn fetch_versioned(&self, name: &str) -> anyhow::Result<(Values, Option<String>)> {
+        let response = self
+            .client
+            .get(self.url(name))
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .with_context(|| format!("failed to read secret {name} from vault"))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok((Values::new(), None));
+        }
+
+        let response = response
+            .error_for_status()
+            .with_context(|| format!("failed to read secret {name} from vault"))?;
+        let body: ReadResponse = response
+            .json()
+            .await
+            .with_context(|| format!("secret {name} is not a kv v2 secret"))?;
+
+        Ok((body.data.data, Some(body.data.metadata.version.to_string())))
+    }
+
+    // NOTE:
+    // Vault only writes when the check-and-set version is the current one, 0 means the secret
+    // must not exist yet
+    async fn store(
+        &self,
+        name: &str,
+        values: &Values,
+        version: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let cas: u64 = version.map_or(Ok(0), str::parse)?;
+
+        let response = self
+            .client
+            .post(self.url(name))
+            .header("X-Vault-Token", &self.token)
+            .json(&serde_json::json!({ "options": { "cas": cas }, "data": values }))
+            .send()
+            .await
+            .with_context(|| format!("failed to write secret {name} to vault"))?;
+
+        if response.status() == StatusCode::BAD_REQUEST {
+            let body = response.text().await.unwrap_or_default();
+            if body.contains("check-and-set") {
+                return Err(Conflict.into());
+            }
+            anyhow::bail!("failed to write secret {name} to vault: {body}");
+        }
+
+        response
+            .error_for_status()
+            .with_context(|| format!("failed to write secret {name} to vault"))?;
+        Ok(())
+    }
+}
+
+/// Answers the first request on `listener` with `status` and `body`, returning the request.
+#[cfg(test)]
+async fn serve_once(listener: tokio::net::TcpListener, status: &str, body: &str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = Vec::new();
+    // the headers and the body can arrive separately
+    loop {
+        let mut buffer = [0; 4096];
+        let read = stream.read(&mut buffer).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+
+        let text = String::from_utf8_lossy(&request).to_lowercase();
+        let Some((headers, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let content_length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length: "))
+            .map_or(0, |length| length.trim().parse().unwrap());
+        if body.len() >= content_length {
+            break;
+        }
+    }
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+
+    String::from_utf8_lossy(&request).to_string()
+}
+
+#[tokio::test]
+async fn test_vault_backend() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}/", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        serve_once(
+            listener,
+            "200 OK",
+            r#"{"data": {"data": {"secret": "test"}, "metadata": {"version": 3}}}"#,
+        )
+        .await
+    });
+
+    let backend = VaultBackend::new(address, String::from("token"), None);
+    let (secrets, version) = backend.fetch_versioned("data-api-secrets").await.unwrap();
+    assert_eq!(secrets["secret"], "test");
+    assert_eq!(version.as_deref(), Some("3"));
+
+    let request = server.await.unwrap().to_lowercase();
+    assert!(request.starts_with("get /v1/secret/data/data-api-secrets "));
+    assert!(request.contains("x-vault-token: token"));
+}
+
+#[tokio::test]
+async fn test_vault_backend_missing_secret() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { serve_once(listener, "404 Not Found", r#"{"errors": []}"#).await });
+
+    let backend = VaultBackend::new(address, String::from("token"), Some(String::from("kv")));
+    assert!(backend.fetch("missing").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_vault_backend_store() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let server =
+        tokio::spawn(
+            async move { serve_once(listener, "200 OK", r#"{"data": {"version": 4}}"#).await },
+        );
+
+    let backend = VaultBackend::new(address, String::from("token"), None);
+    let values = super::parse_json(r#"{"secret": "new"}"#).unwrap();
+    backend
+        .store("data-api-secrets", &values, Some("3"))
+        .await
+        .unwrap();
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /v1/secret/data/data-api-secrets "));
+    assert!(request.ends_with(r#"{"data":{"secret":"new"},"options":{"cas":3}}"#));
+}
+
+#[tokio::test]
+async fn test_vault_backend_store_conflict() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        serve_once(
+            listener,
+            "400 Bad Request",
+            r#"{"errors": ["check-and-set parameter did not match the current version"]}"#,
+        )
+        .await
+    });
+
+    let backend = VaultBackend::new(address, String::from("token"), None);
+    let error = backend
+        .store("data-api-secrets", &Values::new(), None)
+        .await
+        .unwrap_err();
+    assert!(error.is::<Conflict>());
+}
@@ -1,3 +1,6 @@
+mod backend;
+mod namespace;
+mod policy;
 mod provider;
 
 use provider::KeyVaultProvider;
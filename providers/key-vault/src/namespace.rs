@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+/// How the secrets of an application are kept apart from those of other applications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespacing {
+    /// Every application has its own secret, `data-api-secrets-{application id}`.
+    Secret,
+    /// Applications share the secret, their keys are prefixed with `{application id}/`.
+    Prefix,
+}
+
+impl FromStr for Namespacing {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "secret" => Ok(Self::Secret),
+            "prefix" => Ok(Self::Prefix),
+            value => anyhow::bail!("unknown namespacing: {value}"),
+        }
+    }
+}
+
+impl Namespacing {
+    /// The name of the secret and the key within it that hold `key` of the application.
+    pub fn locate(
+        self,
+        secret: &str,
+        application_id: &str,
+        key: &str,
+    ) -> anyhow::Result<(String, String)> {
+        // NOTE:
+        // Ids are used in secret names, paths and prefixes, so they can not be allowed to
+        // point outside of the namespace of the application
+        let valid = !application_id.is_empty()
+            && application_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            anyhow::bail!("invalid application id: {application_id:?}");
+        }
+
+        Ok(match self {
+            Self::Secret => (format!("{secret}-{application_id}"), key.to_string()),
+            Self::Prefix => (secret.to_string(), format!("{application_id}/{key}")),
+        })
+    }
+}
+
+#[test]
+fn test_locate() {
+    assert_eq!(
+        Namespacing::Secret
+            .locate("data-api-secrets", "app-1", "smtp")
+            .unwrap(),
+        (String::from("data-api-secrets-app-1"), String::from("smtp"))
+    );
+    assert_eq!(
+        Namespacing::Prefix
+            .locate("data-api-secrets", "app_1", "smtp")
+            .unwrap(),
+        (String::from("data-api-secrets"), String::from("app_1/smtp"))
+    );
+
+    for application_id in ["", "../app-1", "app-1/other", "app 1"] {
+        assert!(Namespacing::Secret
+            .locate("data-api-secrets", application_id, "smtp")
+            .is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use wasmcloud_provider_sdk::Context;
+
+/// Which keys callers may access, keyed by component id or by `link:{link name}`.
+///
+/// Patterns match a key exactly, a `*` matches any run of characters. Callers without a rule
+/// are denied.
+///
+/// Keys of application secrets are matched as `{application id}/{key}`, where the application
+/// id has to be named in the pattern: `app-1/smtp_*` binds the caller to `app-1`, `*/smtp_*`
+/// does not allow reading the secrets of any application.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    rules: HashMap<String, Vec<String>>,
+}
+
+impl Policy {
+    /// Parses the policy from a JSON object of callers to lists of key patterns.
+    pub fn parse(policy: &str) -> anyhow::Result<Self> {
+        let rules = serde_json::from_str(policy)
+            .context("key-vault policy is not a json object of string lists")?;
+        Ok(Self { rules })
+    }
+
+    pub fn allows(&self, cx: Option<&Context>, key: &str) -> bool {
+        self.patterns(cx).any(|pattern| matches(pattern, key))
+    }
+
+    /// Whether the caller is bound to the application and may read `key` of its secrets.
+    pub fn allows_application(
+        &self,
+        cx: Option<&Context>,
+        application_id: &str,
+        key: &str,
+    ) -> bool {
+        self.patterns(cx)
+            .any(|pattern| match pattern.split_once('/') {
+                Some((application, pattern)) => {
+                    application == application_id && matches(pattern, key)
+                }
+                None => false,
+            })
+    }
+
+    fn patterns<'a>(&'a self, cx: Option<&Context>) -> impl Iterator<Item = &'a String> {
+        let (component, link) = match cx {
+            Some(cx) => (
+                cx.component.as_deref().and_then(|id| self.rules.get(id)),
+                self.rules.get(&format!("link:{}", cx.link_name())),
+            ),
+            None => (None, None),
+        };
+
+        component.into_iter().chain(link).flatten()
+    }
+}
+
+fn matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always returns at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[test]
+fn test_matches() {
+    assert!(matches(
+        "ACTIONS_WASM_DATA_API_SECRET",
+        "ACTIONS_WASM_DATA_API_SECRET"
+    ));
+    assert!(!matches(
+        "ACTIONS_WASM_DATA_API_SECRET",
+        "ACTIONS_WASM_DATA_API_SECRET_2"
+    ));
+    assert!(matches("ACTIONS_WASM_*", "ACTIONS_WASM_CONFIGURATIONS_KEY"));
+    assert!(!matches("ACTIONS_WASM_*", "SMTP_PASSWORD"));
+    assert!(matches("*", "anything"));
+    assert!(matches("*/smtp_*", "app-1/smtp_password"));
+    assert!(!matches("*/smtp_*", "app-1/imap_password"));
+    assert!(matches("a*b*b", "abb"));
+    assert!(!matches("ab*ba", "aba"));
+}
+
+#[test]
+fn test_policy() {
+    let policy = Policy::parse(
+        r#"{
+            "data-api": ["ACTIONS_WASM_*"],
+            "link:actions": ["*/smtp_*"]
+        }"#,
+    )
+    .unwrap();
+    let cx = |component: &str, link_name: &str| Context {
+        component: Some(component.to_string()),
+        tracing: HashMap::from([(String::from("link-name"), link_name.to_string())]),
+    };
+
+    assert!(policy.allows(
+        Some(&cx("data-api", "default")),
+        "ACTIONS_WASM_DATA_API_SECRET"
+    ));
+    assert!(!policy.allows(Some(&cx("data-api", "default")), "app-1/smtp_password"));
+    assert!(policy.allows(Some(&cx("action", "actions")), "app-1/smtp_password"));
+    assert!(!policy.allows(
+        Some(&cx("action", "actions")),
+        "ACTIONS_WASM_DATA_API_SECRET"
+    ));
+    assert!(!policy.allows(Some(&cx("action", "default")), "app-1/smtp_password"));
+    assert!(!policy.allows(None, "ACTIONS_WASM_DATA_API_SECRET"));
+
+    assert!(Policy::parse(r#"{"data-api": "ACTIONS_WASM_*"}"#).is_err());
+}
+
+#[test]
+fn test_policy_allows_application() {
+    let policy = Policy::parse(
+        r#"{
+            "link:app-1": ["app-1/smtp_*"],
+            "link:actions": ["*/smtp_*"]
+        }"#,
+    )
+    .unwrap();
+    let cx = |link_name: &str| Context {
+        component: Some(String::from("action")),
+        tracing: HashMap::from([(String::from("link-name"), link_name.to_string())]),
+    };
+
+    assert!(policy.allows_application(Some(&cx("app-1")), "app-1", "smtp_password"));
+    assert!(!policy.allows_application(Some(&cx("app-1")), "app-1", "imap_password"));
+    assert!(!policy.allows_application(Some(&cx("app-1")), "app-2", "smtp_password"));
+    // a wildcard does not bind the caller to any application
+    assert!(!policy.allows_application(Some(&cx("actions")), "app-1", "smtp_password"));
+    assert!(!policy.allows_application(None, "app-1", "smtp_password"));
+}
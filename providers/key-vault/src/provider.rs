@@ -1,14 +1,23 @@
 use anyhow::Context as _;
-use azure_identity::ManagedIdentityCredential;
-use azure_security_keyvault_secrets::SecretClient;
 use moka::future::Cache;
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
+use tracing::{info, warn};
 use wasmcloud_provider_sdk::{initialize_observability, load_host_data};
 use wasmcloud_provider_sdk::{run_provider, serve_provider_exports, Context, Provider};
 
-use bindings::exports::betty_blocks::key_vault::key_vault::Handler;
-const CACHE_TTL_SECONDS: u64 = 30 * 60 * 60; // 30 minutes
+use crate::backend::{self, Conflict, ReadOnly, SecretBackend, Values};
+use crate::namespace::Namespacing;
+use crate::policy::Policy;
+use bindings::exports::betty_blocks::key_vault::key_vault::{Handler, KeyVaultError};
+const DEFAULT_CACHE_TTL_SECONDS: u64 = 30 * 60; // 30 minutes
+const DEFAULT_REFRESH_INTERVAL_SECONDS: u64 = 5 * 60;
+// Writes read the secret again when it changed concurrently, this many times.
+const MAX_WRITE_ATTEMPTS: u32 = 3;
+
+type Secrets = Arc<Values>;
 
 pub(crate) mod bindings {
     wit_bindgen_wrpc::generate!();
@@ -16,10 +25,17 @@ pub(crate) mod bindings {
 
 #[derive(Debug, Clone)]
 pub struct KeyVaultProvider {
-    endpoint: String,
     key: String,
-    keyvault_mock: Option<String>,
-    cache: Cache<String, String>,
+    backend: Arc<dyn SecretBackend>,
+    // per key-vault secret name
+    cache: Cache<String, Secrets>,
+    namespacing: Namespacing,
+    // `get-secret` reads the secrets of the calling component instead of the shared ones
+    namespace_by_component: bool,
+    // reads are denied when no policy is configured
+    policy: Option<Policy>,
+    // writes are denied when no write policy is configured
+    write_policy: Option<Policy>,
 }
 
 impl KeyVaultProvider {
@@ -27,33 +43,72 @@ impl KeyVaultProvider {
         "key-vault-provider"
     }
 
-    fn new(endpoint: String, key: String, keyvault_mock: Option<String>) -> Self {
-        let cache = Cache::builder()
-            .time_to_live(Duration::from_secs(CACHE_TTL_SECONDS))
-            .build();
+    fn new(key: String, backend: Arc<dyn SecretBackend>, cache_ttl: Duration) -> Self {
+        let cache = Cache::builder().time_to_live(cache_ttl).build();
 
         Self {
-            endpoint,
             key,
-            keyvault_mock,
+            backend,
             cache,
+            namespacing: Namespacing::Secret,
+            namespace_by_component: false,
+            policy: None,
+            write_policy: None,
         }
     }
 
+    fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    fn with_write_policy(mut self, write_policy: Policy) -> Self {
+        self.write_policy = Some(write_policy);
+        self
+    }
+
+    fn with_namespacing(mut self, namespacing: Namespacing, namespace_by_component: bool) -> Self {
+        self.namespacing = namespacing;
+        self.namespace_by_component = namespace_by_component;
+        self
+    }
+
     pub async fn run() -> anyhow::Result<()> {
         initialize_observability!(
             Self::name(),
             std::env::var_os("PROVIDER_KEY_VAULT_FLAMEGRAPH_PATH")
         );
         let host_data = load_host_data().context("failed to load host data")?;
-        let endpoint = host_data
-            .config
-            .get("endpoint")
-            .expect("endpoint is required");
         let key = host_data.config.get("key").expect("key is required");
-        let keyvault_mock = host_data.config.get("keyvault_mock").map(|s| s.to_string());
+        let backend = backend::from_config(&host_data.config)?;
+        let cache_ttl = Duration::from_secs(config_value(
+            &host_data.config,
+            "cache_ttl_seconds",
+            DEFAULT_CACHE_TTL_SECONDS,
+        ));
+        let refresh_interval = Duration::from_secs(config_value(
+            &host_data.config,
+            "refresh_interval_seconds",
+            DEFAULT_REFRESH_INTERVAL_SECONDS,
+        ));
+
+        let namespacing = config_value(&host_data.config, "namespacing", Namespacing::Secret);
+        let namespace_by_component =
+            config_value(&host_data.config, "namespace_by_component", false);
+
+        let mut provider = Self::new(key.to_string(), backend, cache_ttl)
+            .with_namespacing(namespacing, namespace_by_component);
+        if let Some(policy) = host_data.config.get("policy") {
+            provider = provider.with_policy(Policy::parse(policy)?);
+        }
+        if let Some(write_policy) = host_data.config.get("write_policy") {
+            provider = provider.with_write_policy(Policy::parse(write_policy)?);
+        }
+        // 0 disables refreshing, values are then only read again once they expire
+        if !refresh_interval.is_zero() {
+            tokio::spawn(provider.clone().refresh_periodically(refresh_interval));
+        }
 
-        let provider = Self::new(endpoint.to_string(), key.to_string(), keyvault_mock);
         let shutdown = run_provider(provider.clone(), Self::name())
             .await
             .context("failed to run provider")?;
@@ -72,30 +127,290 @@ impl KeyVaultProvider {
         .await
     }
 
-    async fn get_secret_from_keyvault(&self, key: &str) -> anyhow::Result<Option<String>> {
-        let credential = ManagedIdentityCredential::new(None)?;
-        let client = SecretClient::new(&self.endpoint, credential.clone(), None)?;
+    /// Re-reads every cached secret at `interval`, so rotated secrets are picked up before the
+    /// cached values expire.
+    async fn refresh_periodically(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        // the first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            self.refresh_cached_secrets().await;
+        }
+    }
+
+    async fn refresh_cached_secrets(&self) {
+        let names: Vec<String> = self
+            .cache
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        for name in names {
+            if let Err(e) = self.refresh_secrets(&name).await {
+                warn!("failed to refresh secret {name}, keeping the cached values: {e:#}");
+            }
+        }
+    }
+
+    /// The values of the key-vault secret `name`, read from the key-vault on a cache miss.
+    ///
+    /// Concurrent misses wait for a single read, the whole JSON object is cached so misses for
+    /// other keys do not download it again.
+    async fn secrets(&self, name: &str) -> anyhow::Result<Secrets> {
+        self.cache
+            .try_get_with_by_ref(name, self.fetch_secrets(name))
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:#}"))
+    }
+
+    /// Reads the key-vault secret `name` now, replacing the cached values.
+    async fn refresh_secrets(&self, name: &str) -> anyhow::Result<Secrets> {
+        let secrets = self.fetch_secrets(name).await?;
+        self.cache.insert(name.to_string(), secrets.clone()).await;
+        Ok(secrets)
+    }
+
+    /// Fails unless the policy allows the caller to read `key`. Every linked component can call
+    /// the key-vault, so nothing is read without a policy, like the jaws secret.
+    fn authorize(&self, cx: Option<&Context>, key: &str) -> Result<(), KeyVaultError> {
+        let allowed = self
+            .policy
+            .as_ref()
+            .is_some_and(|policy| policy.allows(cx, key));
+
+        match allowed {
+            true => Ok(()),
+            false => Err(denied(cx, key)),
+        }
+    }
+
+    /// Fails unless the write policy allows the caller to write `key`. Being allowed to read a
+    /// key does not allow writing it, like the jaws secret.
+    fn authorize_write(&self, cx: Option<&Context>, key: &str) -> Result<(), KeyVaultError> {
+        let allowed = self
+            .write_policy
+            .as_ref()
+            .is_some_and(|write_policy| write_policy.allows(cx, key));
+
+        match allowed {
+            true => Ok(()),
+            false => Err(denied(cx, key)),
+        }
+    }
+
+    /// Fails unless the policy binds the caller to the application. The application id is
+    /// passed by the caller, so the secrets of applications are never read without a policy.
+    fn authorize_application(
+        &self,
+        cx: Option<&Context>,
+        application_id: &str,
+        key: &str,
+    ) -> Result<(), KeyVaultError> {
+        let allowed = self
+            .policy
+            .as_ref()
+            .is_some_and(|policy| policy.allows_application(cx, application_id, key));
+
+        match allowed {
+            true => Ok(()),
+            false => Err(denied(cx, &format!("{application_id}/{key}"))),
+        }
+    }
+
+    /// The name of the secret and the key within it that `key` is read from, for a request
+    /// made with `cx`.
+    fn locate(&self, cx: Option<&Context>, key: String) -> Result<(String, String), KeyVaultError> {
+        self.authorize(cx, &key)?;
+        self.namespace(cx, key)
+    }
+
+    /// Like `locate`, for writing `key`.
+    fn locate_for_write(
+        &self,
+        cx: Option<&Context>,
+        key: String,
+    ) -> Result<(String, String), KeyVaultError> {
+        self.authorize_write(cx, &key)?;
+        self.namespace(cx, key)
+    }
+
+    fn namespace(
+        &self,
+        cx: Option<&Context>,
+        key: String,
+    ) -> Result<(String, String), KeyVaultError> {
+        if !self.namespace_by_component {
+            // NOTE:
+            // The keys of applications share the secret when they are prefixed, they are only
+            // read through `get-application-secret` which checks the caller is bound to them
+            if self.namespacing == Namespacing::Prefix && key.contains('/') {
+                return Err(denied(cx, &key));
+            }
+            return Ok((self.key.clone(), key));
+        }
+
+        // NOTE:
+        // Refused instead of falling back to the shared secrets, they are not meant for callers
+        // that can not be identified
+        let component = cx.and_then(|cx| cx.component.as_deref()).ok_or_else(|| {
+            KeyVaultError::Denied(String::from("the calling component is unknown"))
+        })?;
+        self.namespacing
+            .locate(&self.key, component, &key)
+            .map_err(|e| KeyVaultError::Invalid(e.to_string()))
+    }
+
+    /// The value of `key` in secret `name`, read from the backend when it is not cached or
+    /// `refresh` is set.
+    async fn value(
+        &self,
+        name: &str,
+        key: &str,
+        refresh: bool,
+    ) -> Result<Option<String>, KeyVaultError> {
+        let secrets = match refresh {
+            true => self.refresh_secrets(name).await,
+            false => self.secrets(name).await,
+        };
+
+        secrets
+            .map(|secrets| backend::lookup(&secrets, key))
+            .map_err(|e| KeyVaultError::Unavailable(format!("{e:#}")))
+    }
 
-        let secret_response = client.get_secret(&self.key, None).await?;
-        let secret_ref = secret_response.into_body().await?;
-        if let Some(secret) = secret_ref.value {
-            return self.get_from_json(&secret, key).await;
+    /// Applies `change` to the values of secret `name` and writes them back. The secret is read
+    /// again when it changed in the meantime, so concurrent writes do not overwrite each other.
+    async fn update_secret(
+        &self,
+        name: &str,
+        change: impl Fn(&mut Values),
+    ) -> Result<(), KeyVaultError> {
+        for _ in 0..MAX_WRITE_ATTEMPTS {
+            let (mut values, version) = self
+                .backend
+                .fetch_versioned(name)
+                .await
+                .map_err(|e| KeyVaultError::Unavailable(format!("{e:#}")))?;
+            change(&mut values);
+
+            match self.backend.store(name, &values, version.as_deref()).await {
+                Ok(()) => {
+                    self.cache.invalidate(name).await;
+                    return Ok(());
+                }
+                Err(e) if e.is::<Conflict>() => {
+                    info!(name, "key-vault secret changed while writing it, retrying");
+                }
+                Err(e) if e.is::<ReadOnly>() => {
+                    return Err(KeyVaultError::Unsupported(format!(
+                        "secret {name} can not be written: {e}"
+                    )));
+                }
+                Err(e) => return Err(KeyVaultError::Unavailable(format!("{e:#}"))),
+            }
         }
 
-        Ok(None)
+        Err(KeyVaultError::Conflict(format!(
+            "secret {name} kept changing while writing it"
+        )))
     }
 
-    async fn get_from_json(&self, json: &str, key: &str) -> anyhow::Result<Option<String>> {
-        let value = serde_json::from_str::<HashMap<String, String>>(json)?
-            .get(key)
-            .cloned();
+    async fn fetch_secrets(&self, name: &str) -> anyhow::Result<Secrets> {
+        Ok(Arc::new(self.backend.fetch(name).await?))
+    }
 
-        if let Some(value) = value {
-            self.cache.insert(key.to_string(), value.clone()).await;
-            return Ok(Some(value));
+    async fn inner_get_secret(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> Result<Option<String>, KeyVaultError> {
+        let (name, key) = self.locate(cx.as_ref(), key)?;
+        self.value(&name, &key, false).await
+    }
+
+    async fn inner_get_secrets(
+        &self,
+        cx: Option<Context>,
+        keys: Vec<String>,
+    ) -> Result<Vec<(String, Option<String>)>, KeyVaultError> {
+        let mut secrets = Vec::with_capacity(keys.len());
+
+        // NOTE:
+        // Keys of the same secret are read from the same cached secret, so this does not read
+        // from the backend more than once per secret
+        for key in keys {
+            let (name, located_key) = self.locate(cx.as_ref(), key.clone())?;
+            let value = self.value(&name, &located_key, false).await?;
+            secrets.push((key, value));
         }
 
-        Ok(None)
+        Ok(secrets)
+    }
+
+    async fn inner_get_application_secret(
+        &self,
+        cx: Option<Context>,
+        application_id: String,
+        key: String,
+    ) -> Result<Option<String>, KeyVaultError> {
+        let (name, located_key) = self
+            .namespacing
+            .locate(&self.key, &application_id, &key)
+            .map_err(|e| KeyVaultError::Invalid(e.to_string()))?;
+        self.authorize_application(cx.as_ref(), &application_id, &key)?;
+        self.value(&name, &located_key, false).await
+    }
+
+    // NOTE:
+    // The values are cached per key-vault secret, so this drops the other keys of it as well
+    async fn inner_invalidate(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> Result<(), KeyVaultError> {
+        let (name, _) = self.locate(cx.as_ref(), key)?;
+        info!(name, "invalidating cached key-vault secret");
+        self.cache.invalidate(&name).await;
+        Ok(())
+    }
+
+    // NOTE:
+    // Keys are written as is, `smtp.host` does not write into a nested `smtp` object. Keys
+    // containing dots win over nested objects, so the written value is the one that is read.
+    async fn inner_set_secret(
+        &self,
+        cx: Option<Context>,
+        key: String,
+        value: String,
+    ) -> Result<(), KeyVaultError> {
+        let (name, key) = self.locate_for_write(cx.as_ref(), key)?;
+        self.update_secret(&name, |values| {
+            values.insert(key.clone(), value.clone().into());
+        })
+        .await
+    }
+
+    async fn inner_delete_secret(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> Result<(), KeyVaultError> {
+        let (name, key) = self.locate_for_write(cx.as_ref(), key)?;
+        self.update_secret(&name, |values| {
+            backend::remove(values, &key);
+        })
+        .await
+    }
+
+    async fn inner_refresh(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> Result<Option<String>, KeyVaultError> {
+        let (name, key) = self.locate(cx.as_ref(), key)?;
+        self.value(&name, &key, true).await
     }
 }
 
@@ -104,38 +419,124 @@ impl Provider for KeyVaultProvider {}
 impl Handler<Option<Context>> for KeyVaultProvider {
     async fn get_secret(
         &self,
-        _cx: Option<Context>,
+        cx: Option<Context>,
         key: String,
-    ) -> anyhow::Result<Option<String>> {
-        if let Some(value) = self.cache.get(&key).await {
-            return Ok(Some(value));
-        }
+    ) -> anyhow::Result<Result<Option<String>, KeyVaultError>> {
+        Ok(self.inner_get_secret(cx, key).await)
+    }
 
-        if let Some(keyvault_mock) = &self.keyvault_mock {
-            return self.get_from_json(keyvault_mock, &key).await;
-        }
+    async fn get_secrets(
+        &self,
+        cx: Option<Context>,
+        keys: Vec<String>,
+    ) -> anyhow::Result<Result<Vec<(String, Option<String>)>, KeyVaultError>> {
+        Ok(self.inner_get_secrets(cx, keys).await)
+    }
 
-        self.get_secret_from_keyvault(&key).await
+    async fn get_application_secret(
+        &self,
+        cx: Option<Context>,
+        application_id: String,
+        key: String,
+    ) -> anyhow::Result<Result<Option<String>, KeyVaultError>> {
+        Ok(self
+            .inner_get_application_secret(cx, application_id, key)
+            .await)
+    }
+
+    async fn invalidate(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<(), KeyVaultError>> {
+        Ok(self.inner_invalidate(cx, key).await)
+    }
+
+    async fn refresh(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<Option<String>, KeyVaultError>> {
+        Ok(self.inner_refresh(cx, key).await)
+    }
+
+    async fn set_secret(
+        &self,
+        cx: Option<Context>,
+        key: String,
+        value: String,
+    ) -> anyhow::Result<Result<(), KeyVaultError>> {
+        Ok(self.inner_set_secret(cx, key, value).await)
+    }
+
+    async fn delete_secret(
+        &self,
+        cx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<(), KeyVaultError>> {
+        Ok(self.inner_delete_secret(cx, key).await)
+    }
+}
+
+fn denied(cx: Option<&Context>, key: &str) -> KeyVaultError {
+    let component = cx.and_then(|cx| cx.component.as_deref());
+    let link_name = cx.map(Context::link_name);
+    warn!(
+        ?component,
+        ?link_name,
+        key,
+        "denied access to a key-vault key"
+    );
+    KeyVaultError::Denied(format!("access to {key} is denied"))
+}
+
+fn config_value<T: FromStr>(config: &HashMap<String, String>, key: &str, default: T) -> T {
+    match config.get(key) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            warn!("invalid value for {key}: {value}, using the default");
+            default
+        }),
+        None => default,
     }
 }
 
 #[cfg(test)]
-use std::sync::Arc;
+use crate::backend::MockBackend;
+
+// A caller that the test policy allows to read every key.
+#[cfg(test)]
+fn test_cx() -> Option<Context> {
+    Some(Context {
+        component: Some(String::from("test")),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+fn test_policy() -> Policy {
+    Policy::parse(r#"{"test": ["*"]}"#).expect("test policy is valid")
+}
 
 #[tokio::test]
 async fn test_get_secret() -> anyhow::Result<()> {
     let json = r#"{"secret": "test"}"#;
 
+    let mock = MockBackend::new(json.to_string());
     let provider = KeyVaultProvider::new(
-        "https://example.vault.azure.net/".to_string(),
         "my-example-secrets".to_string(),
-        Some(json.to_string()),
-    );
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
 
-    let secret = provider.get_secret(None, "secret".to_string()).await?;
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
     assert_eq!(secret, Some("test".to_string()));
 
-    let secret = provider.get_secret(None, "not_found".to_string()).await?;
+    let secret = provider
+        .get_secret(test_cx(), "not_found".to_string())
+        .await??;
     assert_eq!(secret, None);
 
     Ok(())
@@ -145,40 +546,655 @@ async fn test_get_secret() -> anyhow::Result<()> {
 async fn test_get_secret_sets_cache() -> anyhow::Result<()> {
     let json = r#"{"secret": "test", "other": "test"}"#;
 
+    let mock = MockBackend::new(json.to_string());
     let provider = KeyVaultProvider::new(
-        "https://example.vault.azure.net/".to_string(),
         "my-example-secrets".to_string(),
-        Some(json.to_string()),
-    );
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
 
-    let secret = provider.get_secret(None, "secret".to_string()).await?;
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
     assert_eq!(secret, Some("test".to_string()));
 
-    let cache_data: Vec<(String, String)> = provider
+    let cache_data: Vec<(String, Secrets)> = provider
         .cache
         .iter()
         .map(|(k, v)| (Arc::unwrap_or_clone(k), v))
         .collect();
 
-    // only the key that was fetched gets cached
-    let expected_cache_data = vec![(String::from("secret"), String::from("test"))];
+    // the whole key-vault secret gets cached, not only the key that was fetched
+    let expected_cache_data = vec![(
+        String::from("my-example-secrets"),
+        Arc::new(backend::parse_json(
+            r#"{"secret": "test", "other": "test"}"#,
+        )?),
+    )];
     assert_eq!(cache_data, expected_cache_data);
 
     // double check that it really gets fetched from cache, set mock to empty object
-    let mut empty_mock_provider = provider.clone();
-    empty_mock_provider
-        .keyvault_mock
-        .replace(String::from("{}"));
-
-    let secret = empty_mock_provider
-        .get_secret(None, "secret".to_string())
-        .await?;
+    mock.set("{}");
+
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    assert_eq!(secret, Some("test".to_string()));
+
+    let secret = provider
+        .get_secret(test_cx(), "other".to_string())
+        .await??;
+    assert_eq!(secret, Some("test".to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalidate_and_refresh() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(r#"{"secret": "old", "other": "old"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
+    provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    provider
+        .get_secret(test_cx(), "other".to_string())
+        .await??;
+
+    // the secret is rotated
+    mock.set(r#"{"secret": "new", "other": "new"}"#);
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    assert_eq!(secret, Some("old".to_string()));
+
+    provider
+        .invalidate(test_cx(), "secret".to_string())
+        .await??;
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    assert_eq!(secret, Some("new".to_string()));
+
+    let secret = provider.refresh(test_cx(), "other".to_string()).await??;
+    assert_eq!(secret, Some("new".to_string()));
+    let secret = provider
+        .get_secret(test_cx(), "other".to_string())
+        .await??;
+    assert_eq!(secret, Some("new".to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refresh_cached_secrets() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(r#"{"secret": "old", "other": "old"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
+    provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    provider
+        .get_secret(test_cx(), "other".to_string())
+        .await??;
+
+    // "other" was removed from the key-vault
+    mock.set(r#"{"secret": "new"}"#);
+    provider.refresh_cached_secrets().await;
+
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await??;
+    assert_eq!(secret, Some("new".to_string()));
+    let secret = provider
+        .get_secret(test_cx(), "other".to_string())
+        .await??;
+    assert_eq!(secret, None);
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct CountingBackend {
+    fetches: std::sync::atomic::AtomicU32,
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl SecretBackend for CountingBackend {
+    async fn fetch(&self, _name: &str) -> anyhow::Result<Values> {
+        self.fetches
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        // keeps the read in flight while the other misses come in
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        backend::parse_json(r#"{"a": "1", "b": "2", "c": "3"}"#)
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_share_the_secret() -> anyhow::Result<()> {
+    let backend = Arc::new(CountingBackend::default());
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        backend.clone(),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
+
+    let secrets = futures::future::try_join_all(
+        ["a", "b", "c", "d"].map(|key| provider.get_secret(test_cx(), key.to_string())),
+    )
+    .await?;
+    assert_eq!(
+        secrets.into_iter().collect::<Result<Vec<_>, _>>()?,
+        vec![
+            Some("1".to_string()),
+            Some("2".to_string()),
+            Some("3".to_string()),
+            None
+        ]
+    );
+    assert_eq!(backend.fetches.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_secret_is_not_cached() {
+    let mock = MockBackend::new(String::from("not json"));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(test_policy());
+    assert!(matches!(
+        provider.get_secret(test_cx(), "secret".to_string()).await,
+        Ok(Err(KeyVaultError::Unavailable(_)))
+    ));
+
+    mock.set(r#"{"secret": "test"}"#);
+    let secret = provider
+        .get_secret(test_cx(), "secret".to_string())
+        .await
+        .unwrap()
+        .unwrap();
     assert_eq!(secret, Some("test".to_string()));
+}
+
+#[tokio::test]
+async fn test_get_application_secret() -> anyhow::Result<()> {
+    std::env::set_var(
+        "KEY_VAULT_TEST_NAMESPACE_SECRETS_APP_1",
+        r#"{"smtp": "app-1"}"#,
+    );
+    std::env::set_var(
+        "KEY_VAULT_TEST_NAMESPACE_SECRETS_APP_2",
+        r#"{"smtp": "app-2"}"#,
+    );
+    let policy = Policy::parse(r#"{"data-api": ["app-1/*", "app-2/*"], "action": ["app-1/*"]}"#)?;
+    let provider = KeyVaultProvider::new(
+        "key-vault-test-namespace-secrets".to_string(),
+        Arc::new(crate::backend::EnvBackend),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(policy.clone());
+    let cx = |component: &str| {
+        Some(Context {
+            component: Some(component.to_string()),
+            ..Default::default()
+        })
+    };
+
+    let secret = provider
+        .get_application_secret(cx("data-api"), "app-1".to_string(), "smtp".to_string())
+        .await??;
+    assert_eq!(secret, Some("app-1".to_string()));
+    let secret = provider
+        .get_application_secret(cx("data-api"), "app-2".to_string(), "smtp".to_string())
+        .await??;
+    assert_eq!(secret, Some("app-2".to_string()));
+
+    // callers can only read the secrets of the applications they are bound to
+    assert!(matches!(
+        provider
+            .get_application_secret(cx("action"), "app-2".to_string(), "smtp".to_string())
+            .await,
+        Ok(Err(KeyVaultError::Denied(_)))
+    ));
+    assert!(matches!(
+        provider
+            .get_application_secret(cx("data-api"), "../app-2".to_string(), "smtp".to_string())
+            .await,
+        Ok(Err(KeyVaultError::Invalid(_)))
+    ));
+
+    let mock = MockBackend::new(String::from(r#"{"app-1/smtp": "app-1", "smtp": "shared"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_namespacing(Namespacing::Prefix, false);
+
+    // without a policy no caller is bound to an application
+    assert!(matches!(
+        provider
+            .get_application_secret(cx("data-api"), "app-1".to_string(), "smtp".to_string())
+            .await,
+        Ok(Err(KeyVaultError::Denied(_)))
+    ));
+
+    let provider = provider.with_policy(policy);
+    let secret = provider
+        .get_application_secret(cx("data-api"), "app-1".to_string(), "smtp".to_string())
+        .await??;
+    assert_eq!(secret, Some("app-1".to_string()));
+    let secret = provider
+        .get_application_secret(cx("data-api"), "app-2".to_string(), "smtp".to_string())
+        .await??;
+    assert_eq!(secret, None);
+
+    // the prefixed keys of applications can not be read around the application binding
+    assert!(matches!(
+        provider
+            .get_secret(cx("data-api"), "app-1/smtp".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .get_secrets(cx("data-api"), vec![String::from("app-1/smtp")])
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .refresh(cx("data-api"), "app-1/smtp".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .invalidate(cx("data-api"), "app-1/smtp".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_namespace_by_component() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(r#"{"app-1/smtp": "app-1", "smtp": "shared"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_namespacing(Namespacing::Prefix, true)
+    .with_policy(Policy::parse(r#"{"app-1": ["*"], "app-2": ["*"]}"#)?);
+    let cx = |component: &str| Context {
+        component: Some(component.to_string()),
+        ..Default::default()
+    };
 
-    let secret = empty_mock_provider
-        .get_secret(None, "other".to_string())
-        .await?;
+    let secret = provider
+        .get_secret(Some(cx("app-1")), "smtp".to_string())
+        .await??;
+    assert_eq!(secret, Some("app-1".to_string()));
+    let secret = provider
+        .get_secret(Some(cx("app-2")), "smtp".to_string())
+        .await??;
     assert_eq!(secret, None);
 
+    // the shared secrets are not served to callers that can not be identified
+    assert!(matches!(
+        provider.get_secret(None, "smtp".to_string()).await,
+        Ok(Err(KeyVaultError::Denied(_)))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_policy_denies_keys() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(
+        r#"{"ACTIONS_WASM_DATA_API_SECRET": "jaws", "app-1/smtp": "smtp"}"#,
+    ));
+    let policy = Policy::parse(
+        r#"{
+            "data-api": ["ACTIONS_WASM_*"],
+            "link:actions": ["app-1/smtp"]
+        }"#,
+    )?;
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_namespacing(Namespacing::Prefix, false)
+    .with_policy(policy);
+    let cx = |component: &str, link_name: &str| {
+        Some(Context {
+            component: Some(component.to_string()),
+            tracing: HashMap::from([(String::from("link-name"), link_name.to_string())]),
+        })
+    };
+
+    let secret = provider
+        .get_secret(
+            cx("data-api", "default"),
+            "ACTIONS_WASM_DATA_API_SECRET".to_string(),
+        )
+        .await??;
+    assert_eq!(secret, Some("jaws".to_string()));
+    let secret = provider
+        .get_application_secret(
+            cx("action", "actions"),
+            "app-1".to_string(),
+            "smtp".to_string(),
+        )
+        .await??;
+    assert_eq!(secret, Some("smtp".to_string()));
+
+    // customer components can not read the jaws secret
+    assert!(matches!(
+        provider
+            .get_secret(
+                cx("action", "actions"),
+                "ACTIONS_WASM_DATA_API_SECRET".to_string()
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .refresh(
+                cx("action", "default"),
+                "ACTIONS_WASM_DATA_API_SECRET".to_string()
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .get_secret(None, "ACTIONS_WASM_DATA_API_SECRET".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reads_require_a_policy() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(r#"{"ACTIONS_WASM_DATA_API_SECRET": "jaws"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    );
+
+    // without a policy no caller can read the jaws secret
+    assert!(matches!(
+        provider
+            .get_secret(test_cx(), "ACTIONS_WASM_DATA_API_SECRET".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .get_secrets(
+                test_cx(),
+                vec![String::from("ACTIONS_WASM_DATA_API_SECRET")]
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+
+    let provider = provider.with_policy(test_policy());
+    let secret = provider
+        .get_secret(test_cx(), "ACTIONS_WASM_DATA_API_SECRET".to_string())
+        .await??;
+    assert_eq!(secret, Some("jaws".to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_secrets() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(
+        r#"{"smtp": {"username": "betty", "password": "secret", "port": 587}}"#,
+    ));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(Policy::parse(r#"{"action": ["smtp.*"]}"#)?);
+    let cx = || {
+        Some(Context {
+            component: Some(String::from("action")),
+            ..Default::default()
+        })
+    };
+
+    let secrets = provider
+        .get_secrets(
+            cx(),
+            vec![
+                String::from("smtp.username"),
+                String::from("smtp.password"),
+                String::from("smtp.port"),
+                String::from("smtp.host"),
+            ],
+        )
+        .await??;
+    assert_eq!(
+        secrets,
+        vec![
+            (String::from("smtp.username"), Some(String::from("betty"))),
+            (String::from("smtp.password"), Some(String::from("secret"))),
+            (String::from("smtp.port"), Some(String::from("587"))),
+            (String::from("smtp.host"), None),
+        ]
+    );
+
+    // a single denied key fails the whole batch
+    assert!(matches!(
+        provider
+            .get_secrets(
+                cx(),
+                vec![String::from("smtp.username"), String::from("jaws")]
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_and_delete_secret() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(
+        r#"{"token": "old", "other": "test", "smtp": {"host": "smtp.example.com", "port": 587}}"#,
+    ));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(Policy::parse(r#"{"action": ["*"]}"#)?)
+    .with_write_policy(Policy::parse(r#"{"action": ["*"]}"#)?);
+    let cx = || {
+        Some(Context {
+            component: Some(String::from("action")),
+            ..Default::default()
+        })
+    };
+    let secret = provider.get_secret(cx(), "token".to_string()).await??;
+    assert_eq!(secret, Some("old".to_string()));
+
+    provider
+        .set_secret(cx(), "token".to_string(), "new".to_string())
+        .await??;
+    // the cached value is not served anymore
+    let secret = provider.get_secret(cx(), "token".to_string()).await??;
+    assert_eq!(secret, Some("new".to_string()));
+
+    provider
+        .set_secret(cx(), "added".to_string(), "test".to_string())
+        .await??;
+    provider.delete_secret(cx(), "other".to_string()).await??;
+    // nested values are deleted the way they are read
+    provider
+        .delete_secret(cx(), "smtp.host".to_string())
+        .await??;
+    let secret = provider.get_secret(cx(), "smtp.host".to_string()).await??;
+    assert_eq!(secret, None);
+    assert_eq!(
+        mock.fetch("my-example-secrets").await?,
+        backend::parse_json(r#"{"token": "new", "added": "test", "smtp": {"port": 587}}"#)?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_require_a_write_policy() -> anyhow::Result<()> {
+    let mock = MockBackend::new(String::from(r#"{"ACTIONS_WASM_DATA_API_SECRET": "jaws"}"#));
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(mock.clone()),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_policy(Policy::parse(r#"{"action": ["*"]}"#)?);
+    let cx = |component: &str| {
+        Some(Context {
+            component: Some(component.to_string()),
+            ..Default::default()
+        })
+    };
+
+    // without a write policy nothing can be written, even by callers that can read everything
+    let secret = provider
+        .get_secret(cx("action"), "ACTIONS_WASM_DATA_API_SECRET".to_string())
+        .await??;
+    assert_eq!(secret, Some("jaws".to_string()));
+    assert!(matches!(
+        provider
+            .set_secret(
+                cx("action"),
+                "ACTIONS_WASM_DATA_API_SECRET".to_string(),
+                "forged".to_string()
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    assert!(matches!(
+        provider
+            .delete_secret(cx("action"), "ACTIONS_WASM_DATA_API_SECRET".to_string())
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+
+    // reading a key does not allow writing it
+    let provider = provider
+        .with_policy(Policy::parse(
+            r#"{"data-api": ["ACTIONS_WASM_*"], "action": ["oauth_*"]}"#,
+        )?)
+        .with_write_policy(Policy::parse(r#"{"action": ["oauth_*"]}"#)?);
+    let secret = provider
+        .get_secret(cx("data-api"), "ACTIONS_WASM_DATA_API_SECRET".to_string())
+        .await??;
+    assert_eq!(secret, Some("jaws".to_string()));
+    assert!(matches!(
+        provider
+            .set_secret(
+                cx("data-api"),
+                "ACTIONS_WASM_DATA_API_SECRET".to_string(),
+                "forged".to_string()
+            )
+            .await?,
+        Err(KeyVaultError::Denied(_))
+    ));
+    provider
+        .set_secret(cx("action"), "oauth_token".to_string(), "token".to_string())
+        .await??;
+
+    assert_eq!(
+        mock.fetch("my-example-secrets").await?,
+        backend::parse_json(r#"{"ACTIONS_WASM_DATA_API_SECRET": "jaws", "oauth_token": "token"}"#)?
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct AlwaysChangingBackend;
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl SecretBackend for AlwaysChangingBackend {
+    async fn fetch(&self, _name: &str) -> anyhow::Result<Values> {
+        Ok(Values::new())
+    }
+
+    async fn store(
+        &self,
+        _name: &str,
+        _values: &Values,
+        _version: Option<&str>,
+    ) -> anyhow::Result<()> {
+        Err(Conflict.into())
+    }
+}
+
+#[tokio::test]
+async fn test_set_secret_errors() -> anyhow::Result<()> {
+    let write_policy = Policy::parse(r#"{"action": ["*"]}"#)?;
+    let cx = || {
+        Some(Context {
+            component: Some(String::from("action")),
+            ..Default::default()
+        })
+    };
+
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(AlwaysChangingBackend),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_write_policy(write_policy.clone());
+    assert!(matches!(
+        provider
+            .set_secret(cx(), "token".to_string(), "new".to_string())
+            .await?,
+        Err(KeyVaultError::Conflict(_))
+    ));
+
+    let provider = KeyVaultProvider::new(
+        "my-example-secrets".to_string(),
+        Arc::new(crate::backend::EnvBackend),
+        Duration::from_secs(DEFAULT_CACHE_TTL_SECONDS),
+    )
+    .with_write_policy(write_policy);
+    assert!(matches!(
+        provider.delete_secret(cx(), "token".to_string()).await?,
+        Err(KeyVaultError::Unsupported(_))
+    ));
+
     Ok(())
 }